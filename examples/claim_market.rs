@@ -38,7 +38,14 @@ async fn main() {
     &PROGRAM_ID,
   );
 
-  let instruction = claim_market(PROGRAM_ID, claimer_pubkey, market_pubkey, prediction_pubkey);
+  let instruction = claim_market(
+    PROGRAM_ID,
+    claimer_pubkey,
+    market_pubkey,
+    prediction_pubkey,
+    None,
+    None,
+  );
   let mut transaction = Transaction::new_with_payer(&[instruction], Some(&claimer_pubkey));
   transaction.sign(&[&claimer], client.get_latest_blockhash().await.unwrap());
   match client.send_and_confirm_transaction(&transaction).await {
@@ -1,5 +1,5 @@
-use hit_my_bet::instruction::init_prediction_market;
-use hit_my_bet::state::PredictionMarket;
+use hit_my_bet::instruction::{init_prediction_market, MarketParams};
+use hit_my_bet::state::{MarketKind, MarketPricing, PredictionMarket, ProgramConfig};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::clock::UnixTimestamp;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
@@ -45,6 +45,9 @@ async fn main() {
     &PROGRAM_ID,
   );
 
+  let (config_pubkey, _) =
+    Pubkey::find_program_address(&[ProgramConfig::SEED_PREFIX.as_bytes()], &PROGRAM_ID);
+
   let timestamp = std::time::SystemTime::now()
     .add(Duration::from_secs(3600))
     .duration_since(std::time::UNIX_EPOCH)
@@ -55,8 +58,13 @@ async fn main() {
     creator_pubkey,
     market_pubkey,
     resolver_pubkey,
-    market_id.clone(),
+    config_pubkey,
+    *market_id,
     UnixTimestamp::try_from(timestamp).expect("unix"),
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
   );
   let mut transaction = Transaction::new_with_payer(&[instruction], Some(&creator_pubkey));
   transaction.sign(
@@ -71,7 +79,7 @@ async fn main() {
 
 async fn airdrop(client: &RpcClient, address: &Pubkey, amount: u64) -> Signature {
   let signature = client
-    .request_airdrop(&address, amount)
+    .request_airdrop(address, amount)
     .await
     .expect("airdrop");
   for _ in 0..15 {
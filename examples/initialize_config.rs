@@ -0,0 +1,63 @@
+use hit_my_bet::instruction::initialize_config;
+use hit_my_bet::state::ProgramConfig;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::SeedDerivable;
+use solana_sdk::transaction::Transaction;
+use solana_sdk::{
+  commitment_config::CommitmentConfig,
+  signature::{Keypair, Signer},
+};
+use std::time::Duration;
+
+const PROGRAM_ID: Pubkey = hit_my_bet::ID;
+
+#[tokio::main]
+async fn main() {
+  let rpc_url = String::from("http://127.0.0.1:8899");
+  let client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+
+  let mut admin_seed: [u8; 32] = Default::default();
+  admin_seed[0] = 0x3;
+  admin_seed[1] = 0x1;
+  let admin = Keypair::from_seed(&admin_seed).expect("admin");
+  let admin_pubkey = admin.pubkey();
+  println!("admin: {admin_pubkey:?}");
+  airdrop(&client, &admin_pubkey, LAMPORTS_PER_SOL).await;
+
+  let (config_pubkey, _) =
+    Pubkey::find_program_address(&[ProgramConfig::SEED_PREFIX.as_bytes()], &PROGRAM_ID);
+
+  // The admin must be the upgrade authority of the deployed program.
+  let instruction = initialize_config(
+    PROGRAM_ID,
+    admin_pubkey,
+    config_pubkey,
+    admin_pubkey,
+    LAMPORTS_PER_SOL / 10,
+    LAMPORTS_PER_SOL / 10,
+  );
+  let mut transaction = Transaction::new_with_payer(&[instruction], Some(&admin_pubkey));
+  transaction.sign(&[&admin], client.get_latest_blockhash().await.unwrap());
+  match client.send_and_confirm_transaction(&transaction).await {
+    Ok(signature) => println!("Transaction Signature: {}", signature),
+    Err(err) => eprintln!("Error sending transaction: {}", err),
+  }
+}
+
+async fn airdrop(client: &RpcClient, address: &Pubkey, amount: u64) -> Signature {
+  let signature = client
+    .request_airdrop(address, amount)
+    .await
+    .expect("airdrop");
+  for _ in 0..15 {
+    let confirmed = client.confirm_transaction(&signature).await.unwrap();
+    if confirmed {
+      break;
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+  }
+  signature
+}
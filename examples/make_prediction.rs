@@ -49,6 +49,9 @@ async fn main() {
     prediction_pubkey,
     UserVote::Yes,
     5,
+    None,
+    None,
+    None,
   );
   let mut transaction = Transaction::new_with_payer(&[instruction], Some(&voter_pubkey));
   transaction.sign(&[&voter], client.get_latest_blockhash().await.unwrap());
@@ -64,6 +67,9 @@ async fn main() {
     prediction_pubkey,
     UserVote::No,
     3,
+    None,
+    None,
+    None,
   );
   let mut transaction = Transaction::new_with_payer(&[instruction], Some(&voter_pubkey));
   transaction.sign(&[&voter], client.get_latest_blockhash().await.unwrap());
@@ -75,7 +81,7 @@ async fn main() {
 
 async fn airdrop(client: &RpcClient, address: &Pubkey, amount: u64) -> Signature {
   let signature = client
-    .request_airdrop(&address, amount)
+    .request_airdrop(address, amount)
     .await
     .expect("airdrop");
   for _ in 0..15 {
@@ -0,0 +1,86 @@
+//! Constant-product market maker for binary markets.
+//!
+//! The pool holds YES and NO reserves in payout units: every unit of collateral that enters the
+//! pool mints one unit of exposure on both sides, so `reserve + outstanding exposure` of either
+//! side always equals the collateral held for the pool. Trades keep `reserve_yes * reserve_no`
+//! from decreasing, with rounding in favour of the pool.
+
+/// Reserves after a trade, as `(reserve_yes, reserve_no)`.
+pub type Reserves = (u64, u64);
+
+fn oriented(reserves: Reserves, buy_yes: bool) -> (u128, u128) {
+  if buy_yes {
+    (u128::from(reserves.0), u128::from(reserves.1))
+  } else {
+    (u128::from(reserves.1), u128::from(reserves.0))
+  }
+}
+
+fn reoriented(side: u128, other: u128, buy_yes: bool) -> Option<Reserves> {
+  let side = u64::try_from(side).ok()?;
+  let other = u64::try_from(other).ok()?;
+  Some(if buy_yes {
+    (side, other)
+  } else {
+    (other, side)
+  })
+}
+
+/// Collateral needed to take `payout` units of one side out of the pool, rounded up, and the
+/// reserves after the trade.
+pub fn buy(reserves: Reserves, buy_yes: bool, payout: u64) -> Option<(u64, Reserves)> {
+  let (side, other) = oriented(reserves, buy_yes);
+  let payout = u128::from(payout);
+  if side == 0 || other == 0 {
+    return None;
+  }
+  let product = side.checked_mul(other)?;
+  let holds = |cost: u128| -> Option<bool> {
+    let side_after = (side + cost).checked_sub(payout)?;
+    Some(side_after.checked_mul(other + cost)? >= product)
+  };
+
+  // Smallest root of cost^2 + cost * (side + other - payout) - payout * other = 0.
+  let linear = i128::try_from(side + other).ok()? - i128::try_from(payout).ok()?;
+  let discriminant = linear
+    .checked_mul(linear)?
+    .checked_add(i128::try_from(payout.checked_mul(other)?.checked_mul(4)?).ok()?)?;
+  let root = (discriminant.isqrt() - linear).max(0) / 2;
+  let mut cost = u128::try_from(root).ok()?;
+  while !holds(cost).unwrap_or(false) {
+    cost += 1;
+  }
+
+  let side_after = side + cost - payout;
+  let other_after = other + cost;
+  Some((
+    u64::try_from(cost).ok()?,
+    reoriented(side_after, other_after, buy_yes)?,
+  ))
+}
+
+/// Collateral returned for putting `payout` units of one side back into the pool, rounded down,
+/// and the reserves after the trade.
+pub fn sell(reserves: Reserves, sell_yes: bool, payout: u64) -> Option<(u64, Reserves)> {
+  let (side, other) = oriented(reserves, sell_yes);
+  let side = side.checked_add(u128::from(payout))?;
+  let product = u128::from(reserves.0).checked_mul(u128::from(reserves.1))?;
+  let holds = |proceeds: u128| -> Option<bool> {
+    Some((side.checked_sub(proceeds)?).checked_mul(other.checked_sub(proceeds)?)? >= product)
+  };
+
+  // Smallest root of proceeds^2 - proceeds * (side + other) + payout * other = 0.
+  let linear = side.checked_add(other)?;
+  let discriminant = linear
+    .checked_mul(linear)?
+    .checked_sub(u128::from(payout).checked_mul(other)?.checked_mul(4)?)?;
+  let mut proceeds = (linear - discriminant.isqrt()) / 2;
+  while proceeds > 0 && !holds(proceeds).unwrap_or(false) {
+    proceeds -= 1;
+  }
+
+  Some((
+    u64::try_from(proceeds).ok()?,
+    reoriented(side - proceeds, other - proceeds, sell_yes)?,
+  ))
+}
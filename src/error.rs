@@ -26,11 +26,99 @@ pub enum HitMyBetError {
   InvalidResolver,
   #[error("Market is not resolved")]
   MarketIsNotResolved,
+  #[error("Signer is not allowed to change the market resolver")]
+  InvalidResolverAuthority,
+  #[error("Signer is not the program admin")]
+  InvalidAdmin,
+  #[error("Account is not owned by the expected program id")]
+  InvalidAccountOwner,
+  #[error("Operation does not match the market kind")]
+  InvalidMarketKind,
+  #[error("Outcome is out of range for this market")]
+  InvalidOutcome,
+  #[error("Scalar range or value is invalid")]
+  InvalidScalarRange,
+  #[error("Collateral accounts do not match the market")]
+  InvalidCollateral,
+  #[error("Collateral mint has an unsupported extension")]
+  UnsupportedMint,
+  #[error("Share mint does not belong to the market side")]
+  InvalidShareMint,
+  #[error("Instruction is not supported by the market pricing mode")]
+  InvalidPricingMode,
+  #[error("Quoted amount is outside the slippage bound")]
+  SlippageExceeded,
+  #[error("Market has no liquidity")]
+  InsufficientLiquidity,
+  #[error("Prediction holds fewer votes than requested")]
+  InsufficientVotes,
+  #[error("Order book has no free slots")]
+  OrderBookFull,
+  #[error("Order does not exist or is not owned by the signer")]
+  OrderNotFound,
+  #[error("Order price, quantity or counterparty account is invalid")]
+  InvalidOrder,
+  #[error("Best bid is below the best ask")]
+  OrdersNotCrossed,
+  #[error("Bet amount is outside the market bounds")]
+  BetOutOfBounds,
+  #[error("Vote price or bet bounds are invalid")]
+  InvalidBetLimits,
+  #[error("Arithmetic overflow in market accounting")]
+  MathOverflow,
+  #[error("Signer is not the market creator")]
+  InvalidCreator,
+  #[error("Market still has unclaimed positions or open orders")]
+  ClaimsOutstanding,
+  #[error("Claim period must be positive")]
+  InvalidClaimPeriod,
+  #[error("Claim deadline has passed")]
+  ClaimDeadlinePassed,
+  #[error("Claim deadline has not passed yet")]
+  ClaimDeadlineNotReached,
+  #[error("Treasury holds less than requested")]
+  InsufficientTreasuryFunds,
+  #[error("Market fees exceed the maximum")]
+  InvalidFees,
+  #[error("Dispute period must be positive")]
+  InvalidDisputePeriod,
+  #[error("Market resolution is not final yet")]
+  ResolutionNotFinal,
+  #[error("Market resolution cannot be disputed")]
+  ResolutionNotDisputable,
+  #[error("Resolution bond must be positive and needs a dispute period")]
+  InvalidResolutionBond,
+  #[error("Market is still open for bets")]
+  MarketIsOpen,
+  #[error("Resolution bonds have not been settled")]
+  BondsOutstanding,
+  #[error("Account is not the bonded proposer or challenger")]
+  InvalidBondAccount,
+  #[error("Resolver set is empty, too large, has duplicates or an invalid threshold")]
+  InvalidResolverSet,
+  #[error("Resolver has already voted")]
+  ResolverAlreadyVoted,
+  #[error("Resolver votes can no longer reach the threshold")]
+  ResolverSetDeadlocked,
+  #[error("Oracle condition is invalid for the market")]
+  InvalidOracleCondition,
+  #[error("Price feed is not the market oracle or cannot be read")]
+  InvalidPriceFeed,
+  #[error("Price was published before the resolution time or is too old")]
+  StalePrice,
+  #[error("Price confidence interval is too wide")]
+  PriceTooUncertain,
+  #[error("Admin is not the program upgrade authority")]
+  InvalidUpgradeAuthority,
+  #[error("Disputer holds no position in the market")]
+  NotMarketParticipant,
+  #[error("Token account is not owned by the recipient")]
+  InvalidRecipient,
 }
 
-impl Into<u32> for HitMyBetError {
-  fn into(self) -> u32 {
-    match self {
+impl From<HitMyBetError> for u32 {
+  fn from(value: HitMyBetError) -> Self {
+    match value {
       HitMyBetError::IncorrectProgramId => 1,
       HitMyBetError::VersionMismatch => 2,
       HitMyBetError::InstructionUnpackError => 3,
@@ -42,6 +130,50 @@ impl Into<u32> for HitMyBetError {
       HitMyBetError::MarketIsClosed => 9,
       HitMyBetError::InvalidResolver => 10,
       HitMyBetError::MarketIsNotResolved => 11,
+      HitMyBetError::InvalidResolverAuthority => 12,
+      HitMyBetError::InvalidAdmin => 13,
+      HitMyBetError::InvalidAccountOwner => 14,
+      HitMyBetError::InvalidMarketKind => 15,
+      HitMyBetError::InvalidOutcome => 16,
+      HitMyBetError::InvalidScalarRange => 17,
+      HitMyBetError::InvalidCollateral => 18,
+      HitMyBetError::UnsupportedMint => 19,
+      HitMyBetError::InvalidShareMint => 20,
+      HitMyBetError::InvalidPricingMode => 21,
+      HitMyBetError::SlippageExceeded => 22,
+      HitMyBetError::InsufficientLiquidity => 23,
+      HitMyBetError::InsufficientVotes => 24,
+      HitMyBetError::OrderBookFull => 25,
+      HitMyBetError::OrderNotFound => 26,
+      HitMyBetError::InvalidOrder => 27,
+      HitMyBetError::OrdersNotCrossed => 28,
+      HitMyBetError::BetOutOfBounds => 29,
+      HitMyBetError::InvalidBetLimits => 30,
+      HitMyBetError::MathOverflow => 31,
+      HitMyBetError::InvalidCreator => 32,
+      HitMyBetError::ClaimsOutstanding => 33,
+      HitMyBetError::InvalidClaimPeriod => 34,
+      HitMyBetError::ClaimDeadlinePassed => 35,
+      HitMyBetError::ClaimDeadlineNotReached => 36,
+      HitMyBetError::InsufficientTreasuryFunds => 37,
+      HitMyBetError::InvalidFees => 38,
+      HitMyBetError::InvalidDisputePeriod => 39,
+      HitMyBetError::ResolutionNotFinal => 40,
+      HitMyBetError::ResolutionNotDisputable => 41,
+      HitMyBetError::InvalidResolutionBond => 42,
+      HitMyBetError::MarketIsOpen => 43,
+      HitMyBetError::BondsOutstanding => 44,
+      HitMyBetError::InvalidBondAccount => 45,
+      HitMyBetError::InvalidResolverSet => 46,
+      HitMyBetError::ResolverAlreadyVoted => 47,
+      HitMyBetError::ResolverSetDeadlocked => 48,
+      HitMyBetError::InvalidOracleCondition => 49,
+      HitMyBetError::InvalidPriceFeed => 50,
+      HitMyBetError::StalePrice => 51,
+      HitMyBetError::PriceTooUncertain => 52,
+      HitMyBetError::InvalidUpgradeAuthority => 53,
+      HitMyBetError::NotMarketParticipant => 54,
+      HitMyBetError::InvalidRecipient => 55,
     }
   }
 }
@@ -1,22 +1,48 @@
 use crate::error::HitMyBetError;
-use crate::state::{MarketResolution, UserVote, PROGRAM_VERSION};
+use crate::state::{
+  BetLimits, LiquidityPosition, MarketFees, MarketKind, MarketPricing, MarketResolution,
+  OracleCondition, OrderBook, OrderSide, PredictionMarket, ReferrerAccount, ReferrerMintFees,
+  ResolverSet, ResolverSetParams, ScalarPosition, Treasury, UserVote, PROGRAM_VERSION,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::bpf_loader_upgradeable;
 use solana_program::clock::UnixTimestamp;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub enum HitMyBetInstruction {
   InitPredictionMarket {
     version: u8,
     market_id: [u8; 16],
     open_until: UnixTimestamp,
+    kind: MarketKind,
+    pricing: MarketPricing,
+    /// Defaults to the program config vote price with unbounded bets.
+    limits: Option<BetLimits>,
+    /// Seconds that claims stay open after resolution. Claims never expire when omitted.
+    claim_period: Option<UnixTimestamp>,
+    /// Seconds that a resolution by the resolver can be disputed. Resolutions are final at once
+    /// when omitted.
+    dispute_period: Option<UnixTimestamp>,
+    /// Lamports bonded by anyone proposing or disputing a resolution. Only the resolver can
+    /// resolve when omitted. Requires a dispute period.
+    resolution_bond: Option<u64>,
+    /// Resolvers voting on the resolution with `SubmitResolutionVote` instead of `resolver`.
+    resolver_set: Option<ResolverSetParams>,
+    /// Price condition resolving the market with `ResolveFromOracle` instead of `resolver`.
+    oracle: Option<OracleCondition>,
+    /// No fees when omitted.
+    fees: Option<MarketFees>,
   },
   MakePrediction {
     version: u8,
     vote: UserVote,
-    num_votes: u16,
+    num_votes: u64,
+    /// Partner credited with the bet, who receives the market's referral fee.
+    referrer: Option<Pubkey>,
   },
   ResolveMarket {
     version: u8,
@@ -25,13 +51,222 @@ pub enum HitMyBetInstruction {
   ClaimMarket {
     version: u8,
   },
-  SetMarketResolverAdmin {
+  ProposeMarketResolver {
     version: u8,
+    new_resolver: Pubkey,
   },
   ResolveMarketAdmin {
     version: u8,
     resolution: MarketResolution,
   },
+  AcceptMarketResolver {
+    version: u8,
+  },
+  InitializeConfig {
+    version: u8,
+    create_market_fee: u64,
+    vote_price: u64,
+  },
+  UpdateConfig {
+    version: u8,
+    create_market_fee: u64,
+    vote_price: u64,
+  },
+  ProposeAdmin {
+    version: u8,
+    new_admin: Pubkey,
+  },
+  AcceptAdmin {
+    version: u8,
+  },
+  CancelAdminProposal {
+    version: u8,
+  },
+  MakeScalarPrediction {
+    version: u8,
+    position: ScalarPosition,
+    num_votes: u64,
+  },
+  MintCompleteSet {
+    version: u8,
+    num_sets: u64,
+  },
+  RedeemCompleteSet {
+    version: u8,
+    num_sets: u64,
+  },
+  BuyShares {
+    version: u8,
+    vote: UserVote,
+    num_shares: u64,
+    max_cost: u64,
+  },
+  SellShares {
+    version: u8,
+    vote: UserVote,
+    num_shares: u64,
+    min_return: u64,
+  },
+  AddLiquidity {
+    version: u8,
+    amount: u64,
+  },
+  RemoveLiquidity {
+    version: u8,
+    lp_shares: u64,
+  },
+  SellPrediction {
+    version: u8,
+    vote: UserVote,
+    num_votes: u64,
+  },
+  PlaceOrder {
+    version: u8,
+    side: OrderSide,
+    price: u64,
+    quantity: u64,
+  },
+  CancelOrder {
+    version: u8,
+    order_id: u64,
+  },
+  MatchOrders {
+    version: u8,
+  },
+  CloseMarket {
+    version: u8,
+  },
+  SweepUnclaimed {
+    version: u8,
+  },
+  WithdrawTreasury {
+    version: u8,
+    amount: u64,
+  },
+  WithdrawCreatorFees {
+    version: u8,
+  },
+  WithdrawReferralFees {
+    version: u8,
+  },
+  DisputeResolution {
+    version: u8,
+  },
+  FinalizeResolution {
+    version: u8,
+  },
+  SubmitResolutionVote {
+    version: u8,
+    resolution: MarketResolution,
+  },
+  ResolveFromOracle {
+    version: u8,
+  },
+}
+
+/// Optional settings of a new market, each defaulting as described on
+/// [`HitMyBetInstruction::InitPredictionMarket`].
+#[derive(Clone, Default, Debug)]
+pub struct MarketParams {
+  pub limits: Option<BetLimits>,
+  pub claim_period: Option<UnixTimestamp>,
+  pub dispute_period: Option<UnixTimestamp>,
+  pub resolution_bond: Option<u64>,
+  pub resolver_set: Option<ResolverSetParams>,
+  pub oracle: Option<OracleCondition>,
+  pub fees: Option<MarketFees>,
+}
+
+/// Collateral mint, market vault and token program of an SPL Token market.
+#[derive(Clone, Copy, Debug)]
+pub struct CollateralAccounts {
+  pub mint: Pubkey,
+  pub vault: Pubkey,
+  pub token_program: Pubkey,
+  /// Creator token account paying the market maker subsidy of LMSR markets.
+  pub funding_account: Option<Pubkey>,
+}
+
+/// User token account that collateral is paid from or to, together with the market vault.
+#[derive(Clone, Copy, Debug)]
+pub struct UserCollateralAccounts {
+  pub token_account: Pubkey,
+  pub mint: Pubkey,
+  pub vault: Pubkey,
+  pub token_program: Pubkey,
+}
+
+/// User token account holding outcome shares of `share_mint`.
+#[derive(Clone, Copy, Debug)]
+pub struct UserShareAccounts {
+  pub share_mint: Pubkey,
+  pub token_account: Pubkey,
+}
+
+/// Proposer and challenger of a bonded resolution, whose bonds are paid out when the admin settles
+/// the market.
+#[derive(Clone, Copy, Debug)]
+pub struct ResolutionBondAccounts {
+  pub proposer: Pubkey,
+  pub challenger: Option<Pubkey>,
+}
+
+/// User token accounts receiving or giving up the YES and NO shares of a complete set.
+#[derive(Clone, Copy, Debug)]
+pub struct CompleteSetAccounts {
+  pub yes_token_account: Pubkey,
+  pub no_token_account: Pubkey,
+}
+
+impl CollateralAccounts {
+  fn account_metas(&self, program_id: &Pubkey) -> Vec<AccountMeta> {
+    let (treasury_vault, _) = Treasury::find_vault_address(program_id, &self.mint);
+    let mut accounts = vec![
+      AccountMeta::new_readonly(self.mint, false),
+      AccountMeta::new(self.vault, false),
+      AccountMeta::new_readonly(self.token_program, false),
+      AccountMeta::new(treasury_vault, false),
+    ];
+    if let Some(funding_account) = self.funding_account {
+      accounts.push(AccountMeta::new(funding_account, false));
+    }
+    accounts
+  }
+}
+
+impl UserCollateralAccounts {
+  fn account_metas(&self) -> [AccountMeta; 4] {
+    [
+      AccountMeta::new(self.token_account, false),
+      AccountMeta::new_readonly(self.mint, false),
+      AccountMeta::new(self.vault, false),
+      AccountMeta::new_readonly(self.token_program, false),
+    ]
+  }
+}
+
+impl UserShareAccounts {
+  fn account_metas(&self) -> [AccountMeta; 3] {
+    [
+      AccountMeta::new(self.share_mint, false),
+      AccountMeta::new(self.token_account, false),
+      AccountMeta::new_readonly(spl_token::ID, false),
+    ]
+  }
+}
+
+impl CompleteSetAccounts {
+  fn account_metas(&self, program_id: &Pubkey, market_pubkey: &Pubkey) -> [AccountMeta; 5] {
+    let (yes_mint, _) = PredictionMarket::find_share_mint_address(program_id, market_pubkey, 0);
+    let (no_mint, _) = PredictionMarket::find_share_mint_address(program_id, market_pubkey, 1);
+    [
+      AccountMeta::new(yes_mint, false),
+      AccountMeta::new(self.yes_token_account, false),
+      AccountMeta::new(no_mint, false),
+      AccountMeta::new(self.no_token_account, false),
+      AccountMeta::new_readonly(spl_token::ID, false),
+    ]
+  }
 }
 
 impl HitMyBetInstruction {
@@ -47,58 +282,156 @@ impl HitMyBetInstruction {
   }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn init_prediction_market(
   program_id: Pubkey,
   creator: Pubkey,
   market_pubkey: Pubkey,
   resolver: Pubkey,
+  config_pubkey: Pubkey,
   market_id: [u8; 16],
   open_until: UnixTimestamp,
+  kind: MarketKind,
+  pricing: MarketPricing,
+  params: MarketParams,
+  collateral: Option<CollateralAccounts>,
 ) -> Instruction {
+  let (treasury_pubkey, _) = Treasury::find_address(&program_id);
+  let mut accounts = vec![
+    AccountMeta::new(creator, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new_readonly(resolver, true),
+    AccountMeta::new_readonly(config_pubkey, false),
+    AccountMeta::new(treasury_pubkey, false),
+    AccountMeta::new(
+      ResolverSet::find_address(&program_id, &market_pubkey).0,
+      false,
+    ),
+    AccountMeta::new_readonly(solana_program::system_program::ID, false),
+    AccountMeta::new_readonly(spl_token::ID, false),
+  ];
+  accounts.extend((0..kind.num_sides()).map(|side| {
+    let (share_mint, _) =
+      PredictionMarket::find_share_mint_address(&program_id, &market_pubkey, side);
+    AccountMeta::new(share_mint, false)
+  }));
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas(&program_id));
+  }
   Instruction {
     program_id,
-    accounts: vec![
-      AccountMeta::new(creator, true),
-      AccountMeta::new(market_pubkey, false),
-      AccountMeta::new_readonly(resolver, true),
-      AccountMeta::new_readonly(solana_program::system_program::ID, false),
-    ],
+    accounts,
     data: HitMyBetInstruction::InitPredictionMarket {
       version: PROGRAM_VERSION,
       market_id,
       open_until,
+      kind,
+      pricing,
+      limits: params.limits,
+      claim_period: params.claim_period,
+      dispute_period: params.dispute_period,
+      resolution_bond: params.resolution_bond,
+      resolver_set: params.resolver_set,
+      oracle: params.oracle,
+      fees: params.fees,
     }
     .pack()
     .expect("init_prediction_market pack"),
   }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn make_prediction(
   program_id: Pubkey,
   voter: Pubkey,
   market_pubkey: Pubkey,
   user_prediction_pubkey: Pubkey,
   vote: UserVote,
-  num_votes: u16,
+  num_votes: u64,
+  collateral: Option<UserCollateralAccounts>,
+  shares: Option<UserShareAccounts>,
+  referrer: Option<Pubkey>,
 ) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(voter, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(user_prediction_pubkey, false),
+    AccountMeta::new_readonly(solana_program::system_program::ID, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  if let Some(referrer) = referrer {
+    accounts.push(AccountMeta::new(
+      ReferrerAccount::find_address(&program_id, &referrer).0,
+      false,
+    ));
+    if let Some(collateral) = collateral {
+      accounts.push(AccountMeta::new(
+        ReferrerAccount::find_vault_address(&program_id, &referrer, &collateral.mint).0,
+        false,
+      ));
+      accounts.push(AccountMeta::new(
+        ReferrerMintFees::find_address(&program_id, &referrer, &collateral.mint).0,
+        false,
+      ));
+    }
+  }
+  if let Some(shares) = shares {
+    accounts.extend(shares.account_metas());
+  }
   Instruction {
     program_id,
-    accounts: vec![
-      AccountMeta::new(voter, true),
-      AccountMeta::new(market_pubkey, false),
-      AccountMeta::new(user_prediction_pubkey, false),
-      AccountMeta::new_readonly(solana_program::system_program::ID, false),
-    ],
+    accounts,
     data: HitMyBetInstruction::MakePrediction {
       version: PROGRAM_VERSION,
       vote,
       num_votes,
+      referrer,
     }
     .pack()
     .expect("make_prediction pack"),
   }
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn make_scalar_prediction(
+  program_id: Pubkey,
+  voter: Pubkey,
+  market_pubkey: Pubkey,
+  user_prediction_pubkey: Pubkey,
+  position: ScalarPosition,
+  num_votes: u64,
+  collateral: Option<UserCollateralAccounts>,
+  shares: Option<UserShareAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(voter, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(user_prediction_pubkey, false),
+    AccountMeta::new_readonly(solana_program::system_program::ID, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  if let Some(shares) = shares {
+    accounts.extend(shares.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::MakeScalarPrediction {
+      version: PROGRAM_VERSION,
+      position,
+      num_votes,
+    }
+    .pack()
+    .expect("make_scalar_prediction pack"),
+  }
+}
+
+/// Resolves the market as its `resolver`, or as anyone posting the resolution bond on markets
+/// that have one.
 pub fn resolve_market(
   program_id: Pubkey,
   resolver: Pubkey,
@@ -110,6 +443,7 @@ pub fn resolve_market(
     accounts: vec![
       AccountMeta::new(resolver, true),
       AccountMeta::new(market_pubkey, false),
+      AccountMeta::new_readonly(solana_program::system_program::ID, false),
     ],
     data: HitMyBetInstruction::ResolveMarket {
       version: PROGRAM_VERSION,
@@ -120,23 +454,812 @@ pub fn resolve_market(
   }
 }
 
+/// `position` is the disputer's `UserPrediction` or share token account, which markets without a
+/// resolution bond require.
+pub fn dispute_resolution(
+  program_id: Pubkey,
+  disputer: Pubkey,
+  market_pubkey: Pubkey,
+  position: Option<Pubkey>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(disputer, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new_readonly(solana_program::system_program::ID, false),
+  ];
+  if let Some(position) = position {
+    accounts.push(AccountMeta::new_readonly(position, false));
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::DisputeResolution {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("dispute_resolution pack"),
+  }
+}
+
+/// Votes on the resolution of a market resolved by a resolver set.
+pub fn submit_resolution_vote(
+  program_id: Pubkey,
+  resolver: Pubkey,
+  market_pubkey: Pubkey,
+  resolution: MarketResolution,
+) -> Instruction {
+  Instruction {
+    program_id,
+    accounts: vec![
+      AccountMeta::new_readonly(resolver, true),
+      AccountMeta::new(market_pubkey, false),
+      AccountMeta::new(
+        ResolverSet::find_address(&program_id, &market_pubkey).0,
+        false,
+      ),
+    ],
+    data: HitMyBetInstruction::SubmitResolutionVote {
+      version: PROGRAM_VERSION,
+      resolution,
+    }
+    .pack()
+    .expect("submit_resolution_vote pack"),
+  }
+}
+
+/// Resolves an oracle market from its price feed. Anyone can call it once the resolution time has
+/// passed.
+pub fn resolve_from_oracle(
+  program_id: Pubkey,
+  market_pubkey: Pubkey,
+  price_feed: Pubkey,
+) -> Instruction {
+  Instruction {
+    program_id,
+    accounts: vec![
+      AccountMeta::new(market_pubkey, false),
+      AccountMeta::new_readonly(price_feed, false),
+    ],
+    data: HitMyBetInstruction::ResolveFromOracle {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("resolve_from_oracle pack"),
+  }
+}
+
+/// Makes an unchallenged resolution final once its dispute period has ended and returns the bond
+/// of its `proposer`.
+pub fn finalize_resolution(
+  program_id: Pubkey,
+  market_pubkey: Pubkey,
+  proposer: Pubkey,
+) -> Instruction {
+  Instruction {
+    program_id,
+    accounts: vec![
+      AccountMeta::new(market_pubkey, false),
+      AccountMeta::new(proposer, false),
+    ],
+    data: HitMyBetInstruction::FinalizeResolution {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("finalize_resolution pack"),
+  }
+}
+
 pub fn claim_market(
   program_id: Pubkey,
   claimer: Pubkey,
   market_pubkey: Pubkey,
   prediction_pubkey: Pubkey,
+  collateral: Option<UserCollateralAccounts>,
+  shares: Option<UserShareAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(claimer, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(prediction_pubkey, false),
+    AccountMeta::new(
+      LiquidityPosition::find_address(&program_id, &market_pubkey, &claimer).0,
+      false,
+    ),
+    AccountMeta::new(Treasury::find_address(&program_id).0, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+    accounts.push(AccountMeta::new(
+      Treasury::find_vault_address(&program_id, &collateral.mint).0,
+      false,
+    ));
+  }
+  if let Some(shares) = shares {
+    accounts.extend(shares.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::ClaimMarket {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("claim_market pack"),
+  }
+}
+
+pub fn resolve_market_admin(
+  program_id: Pubkey,
+  admin: Pubkey,
+  market_pubkey: Pubkey,
+  config_pubkey: Pubkey,
+  resolution: MarketResolution,
+  bonds: Option<ResolutionBondAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(admin, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new_readonly(config_pubkey, false),
+    AccountMeta::new_readonly(
+      ResolverSet::find_address(&program_id, &market_pubkey).0,
+      false,
+    ),
+  ];
+  if let Some(bonds) = bonds {
+    accounts.push(AccountMeta::new(
+      Treasury::find_address(&program_id).0,
+      false,
+    ));
+    accounts.push(AccountMeta::new(bonds.proposer, false));
+    if let Some(challenger) = bonds.challenger {
+      accounts.push(AccountMeta::new(challenger, false));
+    }
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::ResolveMarketAdmin {
+      version: PROGRAM_VERSION,
+      resolution,
+    }
+    .pack()
+    .expect("resolve_market_admin pack"),
+  }
+}
+
+pub fn propose_market_resolver(
+  program_id: Pubkey,
+  authority: Pubkey,
+  market_pubkey: Pubkey,
+  config_pubkey: Pubkey,
+  new_resolver: Pubkey,
 ) -> Instruction {
   Instruction {
     program_id,
     accounts: vec![
-      AccountMeta::new(claimer, true),
+      AccountMeta::new_readonly(authority, true),
       AccountMeta::new(market_pubkey, false),
-      AccountMeta::new(prediction_pubkey, false),
+      AccountMeta::new_readonly(config_pubkey, false),
     ],
-    data: HitMyBetInstruction::ClaimMarket {
+    data: HitMyBetInstruction::ProposeMarketResolver {
       version: PROGRAM_VERSION,
+      new_resolver,
     }
     .pack()
-    .expect("claim_market pack"),
+    .expect("propose_market_resolver pack"),
+  }
+}
+
+pub fn accept_market_resolver(
+  program_id: Pubkey,
+  new_resolver: Pubkey,
+  market_pubkey: Pubkey,
+) -> Instruction {
+  Instruction {
+    program_id,
+    accounts: vec![
+      AccountMeta::new_readonly(new_resolver, true),
+      AccountMeta::new(market_pubkey, false),
+    ],
+    data: HitMyBetInstruction::AcceptMarketResolver {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("accept_market_resolver pack"),
+  }
+}
+
+pub fn initialize_config(
+  program_id: Pubkey,
+  payer: Pubkey,
+  config_pubkey: Pubkey,
+  admin: Pubkey,
+  create_market_fee: u64,
+  vote_price: u64,
+) -> Instruction {
+  Instruction {
+    program_id,
+    accounts: vec![
+      AccountMeta::new(payer, true),
+      AccountMeta::new(config_pubkey, false),
+      AccountMeta::new_readonly(admin, true),
+      AccountMeta::new_readonly(
+        bpf_loader_upgradeable::get_program_data_address(&program_id),
+        false,
+      ),
+      AccountMeta::new_readonly(solana_program::system_program::ID, false),
+    ],
+    data: HitMyBetInstruction::InitializeConfig {
+      version: PROGRAM_VERSION,
+      create_market_fee,
+      vote_price,
+    }
+    .pack()
+    .expect("initialize_config pack"),
+  }
+}
+
+pub fn update_config(
+  program_id: Pubkey,
+  admin: Pubkey,
+  config_pubkey: Pubkey,
+  create_market_fee: u64,
+  vote_price: u64,
+) -> Instruction {
+  Instruction {
+    program_id,
+    accounts: vec![
+      AccountMeta::new_readonly(admin, true),
+      AccountMeta::new(config_pubkey, false),
+    ],
+    data: HitMyBetInstruction::UpdateConfig {
+      version: PROGRAM_VERSION,
+      create_market_fee,
+      vote_price,
+    }
+    .pack()
+    .expect("update_config pack"),
+  }
+}
+
+pub fn propose_admin(
+  program_id: Pubkey,
+  admin: Pubkey,
+  config_pubkey: Pubkey,
+  new_admin: Pubkey,
+) -> Instruction {
+  Instruction {
+    program_id,
+    accounts: vec![
+      AccountMeta::new_readonly(admin, true),
+      AccountMeta::new(config_pubkey, false),
+    ],
+    data: HitMyBetInstruction::ProposeAdmin {
+      version: PROGRAM_VERSION,
+      new_admin,
+    }
+    .pack()
+    .expect("propose_admin pack"),
+  }
+}
+
+pub fn accept_admin(program_id: Pubkey, new_admin: Pubkey, config_pubkey: Pubkey) -> Instruction {
+  Instruction {
+    program_id,
+    accounts: vec![
+      AccountMeta::new_readonly(new_admin, true),
+      AccountMeta::new(config_pubkey, false),
+    ],
+    data: HitMyBetInstruction::AcceptAdmin {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("accept_admin pack"),
+  }
+}
+
+pub fn cancel_admin_proposal(
+  program_id: Pubkey,
+  admin: Pubkey,
+  config_pubkey: Pubkey,
+) -> Instruction {
+  Instruction {
+    program_id,
+    accounts: vec![
+      AccountMeta::new_readonly(admin, true),
+      AccountMeta::new(config_pubkey, false),
+    ],
+    data: HitMyBetInstruction::CancelAdminProposal {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("cancel_admin_proposal pack"),
+  }
+}
+
+pub fn mint_complete_set(
+  program_id: Pubkey,
+  user: Pubkey,
+  market_pubkey: Pubkey,
+  shares: CompleteSetAccounts,
+  num_sets: u64,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(user, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new_readonly(solana_program::system_program::ID, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  accounts.extend(shares.account_metas(&program_id, &market_pubkey));
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::MintCompleteSet {
+      version: PROGRAM_VERSION,
+      num_sets,
+    }
+    .pack()
+    .expect("mint_complete_set pack"),
+  }
+}
+
+pub fn redeem_complete_set(
+  program_id: Pubkey,
+  user: Pubkey,
+  market_pubkey: Pubkey,
+  shares: CompleteSetAccounts,
+  num_sets: u64,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(user, true),
+    AccountMeta::new(market_pubkey, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  accounts.extend(shares.account_metas(&program_id, &market_pubkey));
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::RedeemCompleteSet {
+      version: PROGRAM_VERSION,
+      num_sets,
+    }
+    .pack()
+    .expect("redeem_complete_set pack"),
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn buy_shares(
+  program_id: Pubkey,
+  buyer: Pubkey,
+  market_pubkey: Pubkey,
+  vote: UserVote,
+  num_shares: u64,
+  max_cost: u64,
+  shares: UserShareAccounts,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(buyer, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new_readonly(solana_program::system_program::ID, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  accounts.extend(shares.account_metas());
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::BuyShares {
+      version: PROGRAM_VERSION,
+      vote,
+      num_shares,
+      max_cost,
+    }
+    .pack()
+    .expect("buy_shares pack"),
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn sell_shares(
+  program_id: Pubkey,
+  seller: Pubkey,
+  market_pubkey: Pubkey,
+  vote: UserVote,
+  num_shares: u64,
+  min_return: u64,
+  shares: UserShareAccounts,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(seller, true),
+    AccountMeta::new(market_pubkey, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  accounts.extend(shares.account_metas());
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::SellShares {
+      version: PROGRAM_VERSION,
+      vote,
+      num_shares,
+      min_return,
+    }
+    .pack()
+    .expect("sell_shares pack"),
+  }
+}
+
+pub fn add_liquidity(
+  program_id: Pubkey,
+  provider: Pubkey,
+  market_pubkey: Pubkey,
+  amount: u64,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let (position_pubkey, _) =
+    LiquidityPosition::find_address(&program_id, &market_pubkey, &provider);
+  let mut accounts = vec![
+    AccountMeta::new(provider, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(position_pubkey, false),
+    AccountMeta::new_readonly(solana_program::system_program::ID, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::AddLiquidity {
+      version: PROGRAM_VERSION,
+      amount,
+    }
+    .pack()
+    .expect("add_liquidity pack"),
+  }
+}
+
+pub fn remove_liquidity(
+  program_id: Pubkey,
+  provider: Pubkey,
+  market_pubkey: Pubkey,
+  lp_shares: u64,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let (position_pubkey, _) =
+    LiquidityPosition::find_address(&program_id, &market_pubkey, &provider);
+  let mut accounts = vec![
+    AccountMeta::new(provider, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(position_pubkey, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::RemoveLiquidity {
+      version: PROGRAM_VERSION,
+      lp_shares,
+    }
+    .pack()
+    .expect("remove_liquidity pack"),
+  }
+}
+
+pub fn sell_prediction(
+  program_id: Pubkey,
+  voter: Pubkey,
+  market_pubkey: Pubkey,
+  user_prediction_pubkey: Pubkey,
+  vote: UserVote,
+  num_votes: u64,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(voter, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(user_prediction_pubkey, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::SellPrediction {
+      version: PROGRAM_VERSION,
+      vote,
+      num_votes,
+    }
+    .pack()
+    .expect("sell_prediction pack"),
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn place_order(
+  program_id: Pubkey,
+  owner: Pubkey,
+  market_pubkey: Pubkey,
+  side: OrderSide,
+  price: u64,
+  quantity: u64,
+  collateral: Option<UserCollateralAccounts>,
+  shares: Option<UserShareAccounts>,
+) -> Instruction {
+  let (order_book_pubkey, _) = OrderBook::find_address(&program_id, &market_pubkey);
+  let mut accounts = vec![
+    AccountMeta::new(owner, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(order_book_pubkey, false),
+    AccountMeta::new_readonly(solana_program::system_program::ID, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  if let Some(shares) = shares {
+    accounts.extend(shares.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::PlaceOrder {
+      version: PROGRAM_VERSION,
+      side,
+      price,
+      quantity,
+    }
+    .pack()
+    .expect("place_order pack"),
+  }
+}
+
+/// Cancels order `order_id` of `owner`, signed by `signer`. Anyone other than the owner can only
+/// cancel once the market resolution is final, with `collateral` and `shares` set to accounts of
+/// the owner.
+pub fn cancel_order(
+  program_id: Pubkey,
+  signer: Pubkey,
+  owner: Pubkey,
+  market_pubkey: Pubkey,
+  order_id: u64,
+  collateral: Option<UserCollateralAccounts>,
+  shares: Option<UserShareAccounts>,
+) -> Instruction {
+  let (order_book_pubkey, _) = OrderBook::find_address(&program_id, &market_pubkey);
+  let mut accounts = vec![
+    AccountMeta::new(signer, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(order_book_pubkey, false),
+    AccountMeta::new(owner, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  if let Some(shares) = shares {
+    accounts.extend(shares.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::CancelOrder {
+      version: PROGRAM_VERSION,
+      order_id,
+    }
+    .pack()
+    .expect("cancel_order pack"),
+  }
+}
+
+/// Matches the best bid against the best ask. `seller` is the owner of the best ask, and
+/// `collateral.token_account` their token account on token markets; `bid_shares` is the share
+/// account of the owner of the best bid.
+pub fn match_orders(
+  program_id: Pubkey,
+  cranker: Pubkey,
+  market_pubkey: Pubkey,
+  seller: Pubkey,
+  bid_shares: UserShareAccounts,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let (order_book_pubkey, _) = OrderBook::find_address(&program_id, &market_pubkey);
+  let mut accounts = vec![
+    AccountMeta::new(cranker, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(order_book_pubkey, false),
+    AccountMeta::new(seller, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  accounts.extend(bid_shares.account_metas());
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::MatchOrders {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("match_orders pack"),
+  }
+}
+
+/// Closes a resolved and fully claimed market of `kind`, with its order book and resolver set, and
+/// revokes the minting of its shares. `collateral.token_account` receives what is left in the
+/// vault of token markets.
+pub fn close_market(
+  program_id: Pubkey,
+  creator: Pubkey,
+  market_pubkey: Pubkey,
+  kind: MarketKind,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let (order_book_pubkey, _) = OrderBook::find_address(&program_id, &market_pubkey);
+  let mut accounts = vec![
+    AccountMeta::new(creator, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new(order_book_pubkey, false),
+    AccountMeta::new(
+      ResolverSet::find_address(&program_id, &market_pubkey).0,
+      false,
+    ),
+    AccountMeta::new_readonly(spl_token::ID, false),
+  ];
+  accounts.extend((0..kind.num_sides()).map(|side| {
+    let (share_mint, _) =
+      PredictionMarket::find_share_mint_address(&program_id, &market_pubkey, side);
+    AccountMeta::new(share_mint, false)
+  }));
+  if let Some(collateral) = collateral {
+    // Transfer fees withheld in the vault are harvested to the mint before it is closed.
+    let mut collateral_metas = collateral.account_metas();
+    collateral_metas[1].is_writable = true;
+    accounts.extend(collateral_metas);
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::CloseMarket {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("close_market pack"),
+  }
+}
+
+/// Moves what is left in a market after its claim deadline to the treasury. On token markets
+/// `collateral.token_account` is the treasury vault of the collateral mint
+/// (see [`Treasury::find_vault_address`]).
+pub fn sweep_unclaimed(
+  program_id: Pubkey,
+  cranker: Pubkey,
+  market_pubkey: Pubkey,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let (order_book_pubkey, _) = OrderBook::find_address(&program_id, &market_pubkey);
+  let (treasury_pubkey, _) = Treasury::find_address(&program_id);
+  let mut accounts = vec![
+    AccountMeta::new(cranker, true),
+    AccountMeta::new(market_pubkey, false),
+    AccountMeta::new_readonly(order_book_pubkey, false),
+    AccountMeta::new(treasury_pubkey, false),
+    AccountMeta::new_readonly(solana_program::system_program::ID, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::SweepUnclaimed {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("sweep_unclaimed pack"),
+  }
+}
+
+/// Withdraws `amount` from the treasury to `recipient`, or from the treasury vault of
+/// `collateral.mint` to `collateral.token_account`, which `recipient` must own, whose `vault` is
+/// that treasury vault.
+pub fn withdraw_treasury(
+  program_id: Pubkey,
+  admin: Pubkey,
+  config_pubkey: Pubkey,
+  recipient: Pubkey,
+  amount: u64,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let (treasury_pubkey, _) = Treasury::find_address(&program_id);
+  let mut accounts = vec![
+    AccountMeta::new_readonly(admin, true),
+    AccountMeta::new_readonly(config_pubkey, false),
+    AccountMeta::new(treasury_pubkey, false),
+    AccountMeta::new(recipient, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::WithdrawTreasury {
+      version: PROGRAM_VERSION,
+      amount,
+    }
+    .pack()
+    .expect("withdraw_treasury pack"),
+  }
+}
+
+/// Pays the creator fees accrued by a market to `creator`, or to `collateral.token_account` on
+/// token markets.
+pub fn withdraw_creator_fees(
+  program_id: Pubkey,
+  creator: Pubkey,
+  market_pubkey: Pubkey,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(creator, true),
+    AccountMeta::new(market_pubkey, false),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::WithdrawCreatorFees {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("withdraw_creator_fees pack"),
+  }
+}
+
+/// Pays the referral fees held for `referrer` to it, or those of a token mint to
+/// `collateral.token_account`, with `collateral.vault` set to the referrer vault of the mint.
+pub fn withdraw_referral_fees(
+  program_id: Pubkey,
+  referrer: Pubkey,
+  collateral: Option<UserCollateralAccounts>,
+) -> Instruction {
+  let mut accounts = vec![
+    AccountMeta::new(referrer, true),
+    AccountMeta::new(
+      ReferrerAccount::find_address(&program_id, &referrer).0,
+      false,
+    ),
+  ];
+  if let Some(collateral) = collateral {
+    accounts.extend(collateral.account_metas());
+    accounts.push(AccountMeta::new(
+      ReferrerMintFees::find_address(&program_id, &referrer, &collateral.mint).0,
+      false,
+    ));
+  }
+  Instruction {
+    program_id,
+    accounts,
+    data: HitMyBetInstruction::WithdrawReferralFees {
+      version: PROGRAM_VERSION,
+    }
+    .pack()
+    .expect("withdraw_referral_fees pack"),
   }
 }
@@ -1,7 +1,11 @@
 #![allow(unexpected_cfgs)]
 
+pub mod cpmm;
 pub mod error;
 pub mod instruction;
+pub mod lmsr;
+pub mod math;
+pub mod oracle;
 pub mod processor;
 pub mod state;
 
@@ -11,6 +15,3 @@ pub mod entrypoint;
 pub use solana_program;
 
 solana_program::declare_id!("H1tBeT1u5GYAdMiXb1xvgSsfxxgAWTBzenKxt8iA2Tzu");
-
-pub const ADMIN_RESOLVER: solana_program::pubkey::Pubkey =
-  solana_program::pubkey::pubkey!("odd9qnGfY6iRzDV9sGLQrgSGxXTRAF6TQoiaYFJaDaR");
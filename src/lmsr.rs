@@ -0,0 +1,118 @@
+//! Fixed-point Logarithmic Market Scoring Rule for binary markets.
+//!
+//! Outstanding shares `q_yes`/`q_no` and the liquidity parameter `b` are counted in shares, each
+//! paying one unit price when its side wins. The cost function
+//! `C(q) = b * ln(e^(q_yes / b) + e^(q_no / b))` is evaluated in the overflow-free form
+//! `max(q) + b * ln(1 + e^(-|q_yes - q_no| / b))` with `ONE`-scaled integers, using range reduction
+//! and short series so that a quote stays well within the compute budget.
+
+/// Fixed-point scale of 1.0.
+pub const ONE: u128 = 1_000_000_000_000_000_000;
+
+/// ln(2) scaled by `ONE`.
+pub const LN_2: u128 = 693_147_180_559_945_309;
+
+/// e^(-x) for `0 <= x < LN_2`.
+fn exp_neg_reduced(x: u128) -> u128 {
+  let mut sum = ONE;
+  let mut term = ONE;
+  let mut n = 1;
+  while term > 0 {
+    term = term * x / ONE / n;
+    if n % 2 == 1 {
+      sum -= term;
+    } else {
+      sum += term;
+    }
+    n += 1;
+  }
+  sum
+}
+
+/// e^(-x) for `x >= 0`.
+fn exp_neg(x: u128) -> u128 {
+  let halvings = x / LN_2;
+  if halvings >= 64 {
+    return 0;
+  }
+  exp_neg_reduced(x - halvings * LN_2) >> halvings
+}
+
+/// ln(1 + y) for `0 <= y <= ONE`, from `ln(1 + y) = 2 * atanh(y / (2 + y))`.
+fn ln_1p(y: u128) -> u128 {
+  let z = y * ONE / (2 * ONE + y);
+  let z_squared = z * z / ONE;
+  let mut sum = 0;
+  let mut power = z;
+  let mut n = 1;
+  while power > 0 {
+    sum += power / n;
+    power = power * z_squared / ONE;
+    n += 2;
+  }
+  2 * sum
+}
+
+/// `C(q)` in shares, scaled by `ONE`.
+pub fn cost(q_yes: u64, q_no: u64, liquidity: u64) -> Option<u128> {
+  if liquidity == 0 {
+    return None;
+  }
+  let spread = u128::from(q_yes.abs_diff(q_no));
+  let x = spread.checked_mul(ONE)? / u128::from(liquidity);
+  let softplus = ln_1p(exp_neg(x));
+  u128::from(q_yes.max(q_no))
+    .checked_mul(ONE)?
+    .checked_add(u128::from(liquidity).checked_mul(softplus)?)
+}
+
+/// Price of a YES share as a probability, scaled by `ONE`.
+pub fn price_yes(q_yes: u64, q_no: u64, liquidity: u64) -> Option<u128> {
+  if liquidity == 0 {
+    return None;
+  }
+  let spread = u128::from(q_yes.abs_diff(q_no));
+  let x = spread.checked_mul(ONE)? / u128::from(liquidity);
+  let odds = exp_neg(x);
+  let cheap_side = odds * ONE / (ONE + odds);
+  Some(if q_yes >= q_no {
+    ONE - cheap_side
+  } else {
+    cheap_side
+  })
+}
+
+/// Collateral owed for moving the outstanding shares from `before` to `after`, rounded up.
+pub fn buy_cost(
+  before: (u64, u64),
+  after: (u64, u64),
+  liquidity: u64,
+  unit_price: u64,
+) -> Option<u64> {
+  let delta =
+    cost(after.0, after.1, liquidity)?.checked_sub(cost(before.0, before.1, liquidity)?)?;
+  let amount = delta.checked_mul(u128::from(unit_price))?;
+  u64::try_from(amount.div_ceil(ONE)).ok()
+}
+
+/// Collateral returned for moving the outstanding shares from `before` to `after`, rounded down.
+pub fn sell_return(
+  before: (u64, u64),
+  after: (u64, u64),
+  liquidity: u64,
+  unit_price: u64,
+) -> Option<u64> {
+  let delta =
+    cost(before.0, before.1, liquidity)?.checked_sub(cost(after.0, after.1, liquidity)?)?;
+  let amount = delta.checked_mul(u128::from(unit_price))?;
+  u64::try_from(amount / ONE).ok()
+}
+
+/// Collateral the creator deposits to cover the maximum market maker loss of `b * ln(2)` unit
+/// prices, rounded up.
+pub fn subsidy(liquidity: u64, unit_price: u64) -> Option<u64> {
+  let amount = u128::from(liquidity)
+    .checked_mul(LN_2)?
+    .checked_mul(u128::from(unit_price))?;
+  u64::try_from(amount.div_ceil(ONE)).ok()
+}
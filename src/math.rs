@@ -0,0 +1,72 @@
+//! Overflow-checked accounting for market pools and payouts.
+//!
+//! Products are taken in u128 before dividing, every division rounds down so that the remainder
+//! stays in the market, and any overflow, underflow or division by zero is reported as
+//! [`HitMyBetError::MathOverflow`] instead of wrapping or panicking.
+
+use crate::error::HitMyBetError;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+pub fn add(a: u64, b: u64) -> Result<u64, HitMyBetError> {
+  a.checked_add(b).ok_or(HitMyBetError::MathOverflow)
+}
+
+pub fn sub(a: u64, b: u64) -> Result<u64, HitMyBetError> {
+  a.checked_sub(b).ok_or(HitMyBetError::MathOverflow)
+}
+
+pub fn mul(a: u64, b: u64) -> Result<u64, HitMyBetError> {
+  a.checked_mul(b).ok_or(HitMyBetError::MathOverflow)
+}
+
+/// Sum of `values`.
+pub fn sum(values: impl IntoIterator<Item = u64>) -> Result<u64, HitMyBetError> {
+  values.into_iter().try_fold(0, add)
+}
+
+/// `value * numerator / denominator`, rounding down.
+pub fn mul_div(value: u64, numerator: u64, denominator: u64) -> Result<u64, HitMyBetError> {
+  let product = u128::from(value) * u128::from(numerator);
+  let quotient = product
+    .checked_div(u128::from(denominator))
+    .ok_or(HitMyBetError::MathOverflow)?;
+  u64::try_from(quotient).map_err(|_| HitMyBetError::MathOverflow)
+}
+
+/// Share of `balance` owed to `votes` out of `total_votes`, rounding down. Pools are credited with
+/// what the vault actually received, so refunds are paid from the pool rather than `vote_price`.
+pub fn pool_share(balance: u64, votes: u64, total_votes: u64) -> Result<u64, HitMyBetError> {
+  if votes == 0 {
+    return Ok(0);
+  }
+  mul_div(balance, votes, total_votes)
+}
+
+/// `bps` basis points of `amount`, rounding down.
+pub fn bps(amount: u64, bps: u64) -> Result<u64, HitMyBetError> {
+  mul_div(amount, bps, BPS_DENOMINATOR)
+}
+
+/// Scalar market payout of a prediction. The whole pool is split between the long (yes) and short
+/// (no) sides linearly by where `value` lands in `[min, max]`, then each side is paid pro-rata.
+/// Both sides must have votes.
+pub fn scalar_payout(
+  balances: (u64, u64),
+  total_votes: (u64, u64),
+  votes: (u64, u64),
+  (min, max): (i64, i64),
+  value: i64,
+) -> Result<u64, HitMyBetError> {
+  let pool = add(balances.0, balances.1)?;
+  let range =
+    u64::try_from(i128::from(max) - i128::from(min)).map_err(|_| HitMyBetError::MathOverflow)?;
+  let offset =
+    u64::try_from(i128::from(value) - i128::from(min)).map_err(|_| HitMyBetError::MathOverflow)?;
+  let long_pool = mul_div(pool, offset, range)?;
+  let short_pool = sub(pool, long_pool)?;
+  add(
+    pool_share(long_pool, votes.0, total_votes.0)?,
+    pool_share(short_pool, votes.1, total_votes.1)?,
+  )
+}
@@ -0,0 +1,101 @@
+//! Price feeds read by `ResolveFromOracle`.
+//!
+//! The processor only sees a [`PriceReading`]; parsing the feed account is left to a
+//! [`PriceFeed`] implementation. The program entrypoint reads Pyth price update accounts, and
+//! tests can run the processor with a feed of their own.
+
+use crate::error::HitMyBetError;
+use borsh::BorshDeserialize;
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::UnixTimestamp;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+/// Oldest price, relative to the current time, that can resolve a market. Also the window after
+/// `resolve_at` in which the price must have been published.
+pub const MAX_PRICE_AGE: UnixTimestamp = 60;
+
+/// Widest confidence interval that can resolve a market, relative to the price.
+pub const MAX_CONFIDENCE_BPS: u64 = 100;
+
+/// Price of the feed `feed_id` at `publish_time`, as `price * 10^exponent` give or take
+/// `confidence`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PriceReading {
+  pub feed_id: [u8; 32],
+  pub price: i64,
+  pub confidence: u64,
+  pub exponent: i32,
+  pub publish_time: UnixTimestamp,
+}
+
+impl PriceReading {
+  /// Whether the confidence interval is within [`MAX_CONFIDENCE_BPS`] of the price.
+  pub fn is_confident(&self) -> bool {
+    u128::from(self.confidence) * 10_000
+      <= u128::from(self.price.unsigned_abs()) * u128::from(MAX_CONFIDENCE_BPS)
+  }
+}
+
+pub trait PriceFeed {
+  /// Reads the latest price of `price_feed_info`, failing if the account is not a feed of this
+  /// kind.
+  fn read(price_feed_info: &AccountInfo) -> Result<PriceReading, ProgramError>;
+}
+
+/// Price update accounts of the Pyth receiver program, with fully verified updates only.
+pub struct PythPriceFeed;
+
+impl PythPriceFeed {
+  pub const OWNER: Pubkey = solana_program::pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+  /// Anchor discriminator of `PriceUpdateV2` accounts.
+  pub const DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+}
+
+#[derive(BorshDeserialize, Debug)]
+pub enum PythVerificationLevel {
+  Partial { num_signatures: u8 },
+  Full,
+}
+
+/// `PriceUpdateV2` account data after the discriminator.
+#[derive(BorshDeserialize, Debug)]
+pub struct PythPriceUpdate {
+  pub write_authority: Pubkey,
+  pub verification_level: PythVerificationLevel,
+  pub feed_id: [u8; 32],
+  pub price: i64,
+  pub conf: u64,
+  pub exponent: i32,
+  pub publish_time: i64,
+  pub prev_publish_time: i64,
+  pub ema_price: i64,
+  pub ema_conf: u64,
+  pub posted_slot: u64,
+}
+
+impl PriceFeed for PythPriceFeed {
+  fn read(price_feed_info: &AccountInfo) -> Result<PriceReading, ProgramError> {
+    if !price_feed_info.owner.eq(&Self::OWNER) {
+      return Err(HitMyBetError::InvalidPriceFeed.into());
+    }
+    let data = price_feed_info.data.borrow();
+    let (discriminator, mut data) = data.split_at_checked(8).unwrap_or_default();
+    if discriminator != Self::DISCRIMINATOR {
+      return Err(HitMyBetError::InvalidPriceFeed.into());
+    }
+    let update: PythPriceUpdate = BorshDeserialize::deserialize(&mut data)
+      .map_err(|_| ProgramError::from(HitMyBetError::InvalidPriceFeed))?;
+    if let PythVerificationLevel::Partial { .. } = update.verification_level {
+      return Err(HitMyBetError::InvalidPriceFeed.into());
+    }
+    Ok(PriceReading {
+      feed_id: update.feed_id,
+      price: update.price,
+      confidence: update.conf,
+      exponent: update.exponent,
+      publish_time: update.publish_time,
+    })
+  }
+}
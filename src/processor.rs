@@ -1,22 +1,41 @@
 use crate::error::HitMyBetError;
 use crate::instruction::HitMyBetInstruction;
+use crate::oracle::{PriceFeed, PythPriceFeed};
 use crate::state::{
-  InitPredictionMarketParams, MarketResolution, PredictionMarket, UserPrediction, UserVote,
-  CREATE_MARKET_FEE, PROGRAM_VERSION, VOTE_PRICE,
+  BetLimits, InitPredictionMarketParams, InitProgramConfigParams, LiquidityPosition, MarketFees,
+  MarketKind, MarketPricing, MarketResolution, OracleCondition, Order, OrderBook, OrderSide,
+  PredictionMarket, ProgramConfig, ReferrerAccount, ReferrerMintFees, ResolutionStatus,
+  ResolverSet, ResolverSetParams, Treasury, UserPrediction, UserVote, MAX_ORDERS, MAX_OUTCOMES,
+  PROGRAM_VERSION,
 };
+use crate::{cpmm, lmsr, math, oracle};
 use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
 use solana_program::clock::UnixTimestamp;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::program::invoke_signed;
+use solana_program::program::{invoke, invoke_signed};
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
 use solana_program::pubkey::Pubkey;
 use solana_program::rent::Rent;
 use solana_program::sysvar::Sysvar;
 use solana_program::{msg, system_instruction};
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use spl_token_2022::state::{Account, Mint};
 
 pub fn process_instruction(
   program_id: &Pubkey,
   accounts: &[AccountInfo],
   input: &[u8],
+) -> ProgramResult {
+  process_instruction_with_price_feed::<PythPriceFeed>(program_id, accounts, input)
+}
+
+/// Processes an instruction reading oracle markets' price feeds as `F`.
+pub fn process_instruction_with_price_feed<F: PriceFeed>(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  input: &[u8],
 ) -> ProgramResult {
   let instruction = HitMyBetInstruction::unpack(input)?;
   match instruction {
@@ -24,16 +43,107 @@ pub fn process_instruction(
       version,
       market_id,
       open_until,
+      kind,
+      pricing,
+      limits,
+      claim_period,
+      dispute_period,
+      resolution_bond,
+      resolver_set,
+      oracle,
+      fees,
     } => {
       if version != PROGRAM_VERSION {
         return Err(HitMyBetError::VersionMismatch.into());
       }
-      process_init_prediction_market(program_id, accounts, &market_id, open_until)
+      if limits.is_some_and(|limits| !limits.is_valid()) {
+        return Err(HitMyBetError::InvalidBetLimits.into());
+      }
+      if claim_period.is_some_and(|claim_period| claim_period <= 0) {
+        return Err(HitMyBetError::InvalidClaimPeriod.into());
+      }
+      if dispute_period.is_some_and(|dispute_period| dispute_period <= 0) {
+        return Err(HitMyBetError::InvalidDisputePeriod.into());
+      }
+      if resolution_bond.is_some_and(|bond| bond == 0 || dispute_period.is_none()) {
+        return Err(HitMyBetError::InvalidResolutionBond.into());
+      }
+      if let Some(resolver_set) = &resolver_set {
+        if !resolver_set.is_valid() {
+          return Err(HitMyBetError::InvalidResolverSet.into());
+        }
+        // Bonded proposals would bypass the quorum.
+        if resolution_bond.is_some() {
+          return Err(HitMyBetError::InvalidResolutionBond.into());
+        }
+      }
+      if let Some(oracle) = &oracle {
+        if kind != MarketKind::Binary {
+          return Err(HitMyBetError::InvalidMarketKind.into());
+        }
+        if oracle.resolve_at < open_until || resolution_bond.is_some() || resolver_set.is_some() {
+          return Err(HitMyBetError::InvalidOracleCondition.into());
+        }
+      }
+      if fees.is_some_and(|fees| !fees.is_valid()) {
+        return Err(HitMyBetError::InvalidFees.into());
+      }
+      // Market maker shares carry no cost basis to tell winnings from returned stake.
+      if pricing != MarketPricing::Parimutuel
+        && fees.is_some_and(|fees| fees.protocol_fee_bps > 0 || fees.creator_fee_bps > 0)
+      {
+        return Err(HitMyBetError::InvalidFees.into());
+      }
+      match pricing {
+        MarketPricing::Parimutuel => {}
+        MarketPricing::Lmsr { liquidity } => {
+          if kind != MarketKind::Binary {
+            return Err(HitMyBetError::InvalidMarketKind.into());
+          }
+          if liquidity == 0 {
+            return Err(HitMyBetError::InvalidPricingMode.into());
+          }
+        }
+        MarketPricing::Cpmm => {
+          if kind != MarketKind::Binary {
+            return Err(HitMyBetError::InvalidMarketKind.into());
+          }
+        }
+      }
+      match kind {
+        MarketKind::Binary => {}
+        MarketKind::Categorical { num_outcomes } => {
+          if num_outcomes < 2 || usize::from(num_outcomes) > MAX_OUTCOMES {
+            return Err(HitMyBetError::InvalidOutcome.into());
+          }
+        }
+        MarketKind::Scalar { min, max } => {
+          if min >= max {
+            return Err(HitMyBetError::InvalidScalarRange.into());
+          }
+        }
+      }
+      process_init_prediction_market(
+        program_id,
+        accounts,
+        &market_id,
+        open_until,
+        kind,
+        pricing,
+        limits,
+        claim_period.unwrap_or(0),
+        dispute_period.unwrap_or(0),
+        resolution_bond.unwrap_or(0),
+        resolver_set.as_ref(),
+        oracle,
+        fees.unwrap_or_default(),
+      )
     }
     HitMyBetInstruction::MakePrediction {
       version,
       vote,
       num_votes,
+      referrer,
     } => {
       if version != PROGRAM_VERSION {
         return Err(HitMyBetError::VersionMismatch.into());
@@ -41,7 +151,7 @@ pub fn process_instruction(
       if let UserVote::Unspecified = vote {
         return Err(HitMyBetError::InstructionUnpackError.into());
       }
-      process_make_prediction(program_id, accounts, vote, num_votes)
+      process_make_prediction(program_id, accounts, vote, num_votes, referrer)
     }
     HitMyBetInstruction::ResolveMarket {
       version,
@@ -61,11 +171,14 @@ pub fn process_instruction(
       }
       process_claim_market(program_id, accounts)
     }
-    HitMyBetInstruction::SetMarketResolverAdmin { version } => {
+    HitMyBetInstruction::ProposeMarketResolver {
+      version,
+      new_resolver,
+    } => {
       if version != PROGRAM_VERSION {
         return Err(HitMyBetError::VersionMismatch.into());
       }
-      process_set_market_resolver_admin(accounts)
+      process_propose_market_resolver(program_id, accounts, new_resolver)
     }
     HitMyBetInstruction::ResolveMarketAdmin {
       version,
@@ -77,22 +190,238 @@ pub fn process_instruction(
       if let MarketResolution::Unresolved = resolution {
         return Err(HitMyBetError::InstructionUnpackError.into());
       }
-      process_resolve_market_admin(accounts, resolution)
+      process_resolve_market_admin(program_id, accounts, resolution)
+    }
+    HitMyBetInstruction::AcceptMarketResolver { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_accept_market_resolver(accounts)
+    }
+    HitMyBetInstruction::InitializeConfig {
+      version,
+      create_market_fee,
+      vote_price,
+    } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      if vote_price == 0 {
+        return Err(HitMyBetError::InvalidBetLimits.into());
+      }
+      process_initialize_config(program_id, accounts, create_market_fee, vote_price)
+    }
+    HitMyBetInstruction::UpdateConfig {
+      version,
+      create_market_fee,
+      vote_price,
+    } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      if vote_price == 0 {
+        return Err(HitMyBetError::InvalidBetLimits.into());
+      }
+      process_update_config(program_id, accounts, create_market_fee, vote_price)
+    }
+    HitMyBetInstruction::ProposeAdmin { version, new_admin } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_propose_admin(program_id, accounts, new_admin)
+    }
+    HitMyBetInstruction::AcceptAdmin { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_accept_admin(program_id, accounts)
+    }
+    HitMyBetInstruction::CancelAdminProposal { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_cancel_admin_proposal(program_id, accounts)
+    }
+    HitMyBetInstruction::MakeScalarPrediction {
+      version,
+      position,
+      num_votes,
+    } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_make_prediction(program_id, accounts, position.into(), num_votes, None)
+    }
+    HitMyBetInstruction::MintCompleteSet { version, num_sets } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_mint_complete_set(program_id, accounts, num_sets)
+    }
+    HitMyBetInstruction::RedeemCompleteSet { version, num_sets } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_redeem_complete_set(program_id, accounts, num_sets)
+    }
+    HitMyBetInstruction::BuyShares {
+      version,
+      vote,
+      num_shares,
+      max_cost,
+    } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_buy_shares(program_id, accounts, vote, num_shares, max_cost)
+    }
+    HitMyBetInstruction::SellShares {
+      version,
+      vote,
+      num_shares,
+      min_return,
+    } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_sell_shares(program_id, accounts, vote, num_shares, min_return)
+    }
+    HitMyBetInstruction::AddLiquidity { version, amount } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_add_liquidity(program_id, accounts, amount)
+    }
+    HitMyBetInstruction::RemoveLiquidity { version, lp_shares } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_remove_liquidity(program_id, accounts, lp_shares)
+    }
+    HitMyBetInstruction::SellPrediction {
+      version,
+      vote,
+      num_votes,
+    } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_sell_prediction(program_id, accounts, vote, num_votes)
+    }
+    HitMyBetInstruction::PlaceOrder {
+      version,
+      side,
+      price,
+      quantity,
+    } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_place_order(program_id, accounts, side, price, quantity)
+    }
+    HitMyBetInstruction::CancelOrder { version, order_id } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_cancel_order(program_id, accounts, order_id)
+    }
+    HitMyBetInstruction::MatchOrders { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_match_orders(program_id, accounts)
+    }
+    HitMyBetInstruction::CloseMarket { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_close_market(program_id, accounts)
+    }
+    HitMyBetInstruction::SweepUnclaimed { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_sweep_unclaimed(program_id, accounts)
+    }
+    HitMyBetInstruction::WithdrawTreasury { version, amount } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_withdraw_treasury(program_id, accounts, amount)
+    }
+    HitMyBetInstruction::WithdrawCreatorFees { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_withdraw_creator_fees(program_id, accounts)
+    }
+    HitMyBetInstruction::WithdrawReferralFees { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_withdraw_referral_fees(program_id, accounts)
+    }
+    HitMyBetInstruction::DisputeResolution { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_dispute_resolution(program_id, accounts)
+    }
+    HitMyBetInstruction::FinalizeResolution { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_finalize_resolution(accounts)
+    }
+    HitMyBetInstruction::SubmitResolutionVote {
+      version,
+      resolution,
+    } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      if let MarketResolution::Unresolved = resolution {
+        return Err(HitMyBetError::InstructionUnpackError.into());
+      }
+      process_submit_resolution_vote(program_id, accounts, resolution)
+    }
+    HitMyBetInstruction::ResolveFromOracle { version } => {
+      if version != PROGRAM_VERSION {
+        return Err(HitMyBetError::VersionMismatch.into());
+      }
+      process_resolve_from_oracle::<F>(accounts)
     }
   }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_init_prediction_market(
   program_id: &Pubkey,
   accounts: &[AccountInfo],
   market_id: &[u8; 16],
   open_until: UnixTimestamp,
+  kind: MarketKind,
+  pricing: MarketPricing,
+  limits: Option<BetLimits>,
+  claim_period: UnixTimestamp,
+  dispute_period: UnixTimestamp,
+  resolution_bond: u64,
+  resolver_set: Option<&ResolverSetParams>,
+  oracle: Option<OracleCondition>,
+  fees: MarketFees,
 ) -> ProgramResult {
   let account_info_iter = &mut accounts.iter();
   let creator_info = next_account_info(account_info_iter)?;
   let prediction_market_info = next_account_info(account_info_iter)?;
   let resolver_info = next_account_info(account_info_iter)?;
+  let config_info = next_account_info(account_info_iter)?;
+  let treasury_info = next_account_info(account_info_iter)?;
+  let resolver_set_info = next_account_info(account_info_iter)?;
   let system_program = next_account_info(account_info_iter)?;
+  let share_token_program = next_account_info(account_info_iter)?;
+  let share_mint_infos = (0..kind.num_sides())
+    .map(|_| next_account_info(account_info_iter))
+    .collect::<Result<Vec<_>, _>>()?;
   if !creator_info.is_signer {
     return Err(HitMyBetError::InvalidSigner.into());
   }
@@ -100,6 +429,37 @@ fn process_init_prediction_market(
     return Err(HitMyBetError::InvalidSigner.into());
   }
   assert_system_program(system_program.key)?;
+  if !spl_token::check_id(share_token_program.key) {
+    return Err(HitMyBetError::IncorrectProgramId.into());
+  }
+  let config = load_program_config(program_id, config_info)?;
+  let limits = limits.unwrap_or(BetLimits {
+    vote_price: config.vote_price,
+    min_bet: 0,
+    max_bet: 0,
+  });
+  // Limits defaulted from the config are checked like explicit ones.
+  if !limits.is_valid() {
+    return Err(HitMyBetError::InvalidBetLimits.into());
+  }
+  let collateral_infos = match account_info_iter.next() {
+    Some(mint_info) => Some((
+      mint_info,
+      next_account_info(account_info_iter)?,
+      next_account_info(account_info_iter)?,
+    )),
+    None => None,
+  };
+  // Protocol fees of token markets are paid into the treasury vault of the collateral mint.
+  let treasury_vault_info = match collateral_infos {
+    Some(_) => Some(next_account_info(account_info_iter)?),
+    None => None,
+  };
+  // The LMSR subsidy is paid from the creator's token account in token markets.
+  let funding_info = match (pricing, collateral_infos) {
+    (MarketPricing::Lmsr { .. }, Some(_)) => Some(next_account_info(account_info_iter)?),
+    _ => None,
+  };
 
   let (prediction_market_pda, prediction_market_bump) = Pubkey::find_program_address(
     &[PredictionMarket::SEED_PREFIX.as_bytes(), market_id.as_ref()],
@@ -113,12 +473,12 @@ fn process_init_prediction_market(
   }
 
   let rent = Rent::get()?;
-  let rent_lamports = rent.minimum_balance(PredictionMarket::LEN) + CREATE_MARKET_FEE;
+  let rent_lamports = rent.minimum_balance(PredictionMarket::LEN);
 
   invoke_signed(
     &system_instruction::create_account(
       creator_info.key,
-      &prediction_market_info.key,
+      prediction_market_info.key,
       rent_lamports,
       u64::try_from(PredictionMarket::LEN).expect("data size"),
       program_id,
@@ -135,11 +495,143 @@ fn process_init_prediction_market(
     ]],
   )?;
 
+  if let Some(params) = resolver_set {
+    let (resolver_set_pda, resolver_set_bump) =
+      ResolverSet::find_address(program_id, prediction_market_info.key);
+    if !resolver_set_info.key.eq(&resolver_set_pda) {
+      return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+    }
+    invoke_signed(
+      &system_instruction::create_account(
+        creator_info.key,
+        resolver_set_info.key,
+        rent.minimum_balance(ResolverSet::LEN),
+        u64::try_from(ResolverSet::LEN).expect("data size"),
+        program_id,
+      ),
+      &[
+        creator_info.clone(),
+        resolver_set_info.clone(),
+        system_program.clone(),
+      ],
+      &[&[
+        ResolverSet::SEED_PREFIX.as_bytes(),
+        prediction_market_info.key.as_ref(),
+        &[resolver_set_bump],
+      ]],
+    )?;
+    borsh::BorshSerialize::serialize(
+      &ResolverSet::with_params(resolver_set_bump, params),
+      &mut &mut resolver_set_info.data.borrow_mut()[..],
+    )?;
+    msg!(
+      "resolver set: threshold={},resolvers={}",
+      params.threshold,
+      params.resolvers.len()
+    );
+  }
+
+  // The creation fee is protocol revenue and never enters the market account.
+  let mut treasury =
+    load_or_create_treasury(program_id, creator_info, treasury_info, system_program)?;
+  if config.create_market_fee > 0 {
+    invoke(
+      &system_instruction::transfer(
+        creator_info.key,
+        treasury_info.key,
+        config.create_market_fee,
+      ),
+      &[
+        creator_info.clone(),
+        treasury_info.clone(),
+        system_program.clone(),
+      ],
+    )?;
+    treasury.total_fees = math::add(treasury.total_fees, config.create_market_fee)?;
+    borsh::BorshSerialize::serialize(&treasury, &mut &mut treasury_info.data.borrow_mut()[..])?;
+  }
+
+  for (side, share_mint_info) in (0..).zip(share_mint_infos) {
+    create_share_mint(
+      program_id,
+      creator_info,
+      prediction_market_info,
+      share_mint_info,
+      share_token_program,
+      system_program,
+      side,
+    )?;
+  }
+
+  let collateral_mint = match collateral_infos {
+    Some((mint_info, vault_info, token_program)) => {
+      // Market maker quotes assume the vault receives every quoted unit.
+      if pricing != MarketPricing::Parimutuel && mint_has_transfer_fee(mint_info)? {
+        return Err(HitMyBetError::UnsupportedMint.into());
+      }
+      create_market_vault(
+        program_id,
+        creator_info,
+        prediction_market_info,
+        mint_info,
+        vault_info,
+        token_program,
+        system_program,
+      )?;
+      if let Some(treasury_vault_info) = treasury_vault_info {
+        create_treasury_vault_if_needed(
+          program_id,
+          creator_info,
+          treasury_info,
+          mint_info,
+          treasury_vault_info,
+          token_program,
+          system_program,
+        )?;
+      }
+      msg!("collateral mint: {}", mint_info.key);
+      Some(*mint_info.key)
+    }
+    None => None,
+  };
+
+  if let MarketPricing::Lmsr { liquidity } = pricing {
+    let subsidy = lmsr::subsidy(liquidity, limits.vote_price).ok_or(HitMyBetError::MathOverflow)?;
+    let funding_collateral = match (collateral_infos, funding_info) {
+      (Some((mint_info, vault_info, token_program)), Some(funding_info)) => Some(CollateralInfos {
+        token_account: funding_info,
+        mint: mint_info,
+        vault: vault_info,
+        token_program,
+      }),
+      _ => None,
+    };
+    deposit_collateral(
+      creator_info,
+      prediction_market_info,
+      funding_collateral.as_ref(),
+      subsidy,
+    )?;
+    msg!("lmsr liquidity={},subsidy={}", liquidity, subsidy);
+  }
+
   borsh::BorshSerialize::serialize(
     &PredictionMarket::with_params(InitPredictionMarketParams {
       bump_seed: prediction_market_bump,
-      resolver: resolver_info.key.clone(),
+      market_id: *market_id,
+      kind,
+      pricing,
+      collateral_mint,
+      creator: *creator_info.key,
+      resolver: *resolver_info.key,
+      has_resolver_set: resolver_set.is_some(),
+      oracle,
+      limits,
+      fees,
       open_until,
+      dispute_period,
+      resolution_bond,
+      claim_period,
     }),
     &mut &mut prediction_market_info.data.borrow_mut()[..],
   )?;
@@ -151,7 +643,8 @@ fn process_make_prediction(
   program_id: &Pubkey,
   accounts: &[AccountInfo],
   vote: UserVote,
-  num_votes: u16,
+  num_votes: u64,
+  referrer: Option<Pubkey>,
 ) -> ProgramResult {
   let account_info_iter = &mut accounts.iter();
   let voter_info = next_account_info(account_info_iter)?;
@@ -179,6 +672,10 @@ fn process_make_prediction(
   let mut market: PredictionMarket =
     borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
 
+  if market.pricing != MarketPricing::Parimutuel {
+    return Err(HitMyBetError::InvalidPricingMode.into());
+  }
+
   if market.resolution != MarketResolution::Unresolved {
     return Err(HitMyBetError::MarketIsResolved.into());
   }
@@ -189,6 +686,106 @@ fn process_make_prediction(
     return Err(HitMyBetError::MarketIsClosed.into());
   }
 
+  match (&vote, market.kind) {
+    (UserVote::Yes | UserVote::No, MarketKind::Binary | MarketKind::Scalar { .. }) => {}
+    (UserVote::Outcome(outcome), MarketKind::Categorical { num_outcomes }) => {
+      if *outcome >= num_outcomes {
+        return Err(HitMyBetError::InvalidOutcome.into());
+      }
+    }
+    _ => return Err(HitMyBetError::InvalidMarketKind.into()),
+  }
+
+  if num_votes == 0 {
+    return Err(HitMyBetError::BetOutOfBounds.into());
+  }
+  let bet_amount = math::mul(num_votes, market.vote_price)?;
+  assert_bet_limits(&market, bet_amount)?;
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  if let Some(referrer) = referrer {
+    pay_referral_fee(
+      program_id,
+      account_info_iter,
+      voter_info,
+      system_program,
+      collateral_infos.as_ref(),
+      &referrer,
+      math::bps(bet_amount, market.referral_fee_bps.into())?,
+    )?;
+  }
+  let bet_amount = deposit_collateral(
+    voter_info,
+    prediction_market_info,
+    collateral_infos.as_ref(),
+    bet_amount,
+  )?;
+
+  match vote {
+    UserVote::Unspecified => {
+      return Err(HitMyBetError::InstructionUnpackError.into());
+    }
+    UserVote::Yes => {
+      market.balance_yes = math::add(market.balance_yes, bet_amount)?;
+      market.num_yes = math::add(market.num_yes, num_votes)?;
+      msg!(
+        "bet={},balance={},num={}",
+        bet_amount,
+        market.balance_yes,
+        market.num_yes
+      );
+    }
+    UserVote::No => {
+      market.balance_no = math::add(market.balance_no, bet_amount)?;
+      market.num_no = math::add(market.num_no, num_votes)?;
+      msg!(
+        "bet={},balance={},num={}",
+        bet_amount,
+        market.balance_no,
+        market.num_no
+      );
+    }
+    UserVote::Outcome(outcome) => {
+      let outcome = usize::from(outcome);
+      market.outcome_balances[outcome] = math::add(market.outcome_balances[outcome], bet_amount)?;
+      market.outcome_votes[outcome] = math::add(market.outcome_votes[outcome], num_votes)?;
+      msg!(
+        "bet={},outcome={},balance={},num={}",
+        bet_amount,
+        outcome,
+        market.outcome_balances[outcome],
+        market.outcome_votes[outcome]
+      );
+    }
+  }
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  // Votes are either minted as outcome shares to the voter's token account or recorded in the
+  // voter's UserPrediction account.
+  let side = vote.side().ok_or(HitMyBetError::InstructionUnpackError)?;
+  if let Some(shares) = next_share_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )? {
+    if shares.side != side {
+      return Err(HitMyBetError::InvalidShareMint.into());
+    }
+    mint_shares(&market, prediction_market_info, &shares, num_votes)?;
+    msg!("minted shares: side={},amount={}", side, num_votes);
+    return Ok(());
+  }
+
   let mut prediction: UserPrediction;
   if user_prediction_info.data_is_empty() {
     prediction = UserPrediction::with_seed(user_prediction_bump);
@@ -199,7 +796,7 @@ fn process_make_prediction(
     invoke_signed(
       &system_instruction::create_account(
         voter_info.key,
-        &user_prediction_info.key,
+        user_prediction_info.key,
         rent_lamports,
         u64::try_from(UserPrediction::LEN).expect("data size"),
         program_id,
@@ -224,249 +821,3558 @@ fn process_make_prediction(
     msg!("existing prediction");
   }
 
-  let bet_amount = u64::from(num_votes) * VOTE_PRICE;
+  add_votes(&mut prediction, &vote, num_votes)?;
+  msg!("prediction: {:?}", prediction);
 
-  invoke_signed(
-    &system_instruction::transfer(voter_info.key, &prediction_market_info.key, bet_amount),
-    &[voter_info.clone(), prediction_market_info.clone()],
-    &[],
+  borsh::BorshSerialize::serialize(
+    &prediction,
+    &mut &mut user_prediction_info.data.borrow_mut()[..],
   )?;
 
-  match vote {
-    UserVote::Unspecified => {
-      return Err(HitMyBetError::InstructionUnpackError.into());
-    }
-    UserVote::Yes => {
-      market.balance_yes += bet_amount;
-      market.num_yes += u64::from(num_votes);
-      prediction.num_votes_yes += u64::from(num_votes);
-      msg!(
-        "bet={},balance={},num={},votes={}",
-        bet_amount,
-        market.balance_yes,
-        market.num_yes,
-        prediction.num_votes_yes
-      );
-    }
-    UserVote::No => {
-      market.balance_no += bet_amount;
-      market.num_no += u64::from(num_votes);
-      prediction.num_votes_no += u64::from(num_votes);
+  Ok(())
+}
+
+/// Reads the trailing `[referrer_account, [referrer_vault, referrer_mint_fees]]` accounts of a
+/// referred bet, the vault and mint fees only on token markets, and pays `fee` from the voter to
+/// the referrer on top of the bet. The referrer accounts are created at the voter's expense on
+/// first use.
+fn pay_referral_fee<'a, 'b: 'a, I: Iterator<Item = &'a AccountInfo<'b>>>(
+  program_id: &Pubkey,
+  account_info_iter: &mut I,
+  voter_info: &AccountInfo<'b>,
+  system_program: &AccountInfo<'b>,
+  collateral: Option<&CollateralInfos<'_, 'b>>,
+  referrer: &Pubkey,
+  fee: u64,
+) -> ProgramResult {
+  let referrer_account_info = next_account_info(account_info_iter)?;
+  let (referrer_account_pda, referrer_account_bump) =
+    ReferrerAccount::find_address(program_id, referrer);
+  if !referrer_account_info.key.eq(&referrer_account_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  let mut referrer_account = if referrer_account_info.data_is_empty() {
+    let rent = Rent::get()?;
+    invoke_signed(
+      &system_instruction::create_account(
+        voter_info.key,
+        referrer_account_info.key,
+        rent.minimum_balance(ReferrerAccount::LEN),
+        u64::try_from(ReferrerAccount::LEN).expect("data size"),
+        program_id,
+      ),
+      &[
+        voter_info.clone(),
+        referrer_account_info.clone(),
+        system_program.clone(),
+      ],
+      &[&[
+        ReferrerAccount::SEED_PREFIX.as_bytes(),
+        referrer.as_ref(),
+        &[referrer_account_bump],
+      ]],
+    )?;
+    ReferrerAccount::with_params(referrer_account_bump, *referrer)
+  } else {
+    assert_market_owner(referrer_account_info.owner)?;
+    borsh::BorshDeserialize::deserialize(&mut referrer_account_info.data.borrow().as_ref())?
+  };
+
+  let received = match collateral {
+    Some(collateral) => {
+      let referrer_vault_info = next_account_info(account_info_iter)?;
+      let (referrer_vault_pda, referrer_vault_bump) =
+        ReferrerAccount::find_vault_address(program_id, referrer, collateral.mint.key);
+      if !referrer_vault_info.key.eq(&referrer_vault_pda) {
+        return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+      }
+      if referrer_vault_info.data_is_empty() {
+        create_token_vault(
+          voter_info,
+          referrer_account_info.key,
+          collateral.mint,
+          referrer_vault_info,
+          collateral.token_program,
+          system_program,
+          &[
+            ReferrerAccount::VAULT_SEED_PREFIX.as_bytes(),
+            referrer.as_ref(),
+            collateral.mint.key.as_ref(),
+            &[referrer_vault_bump],
+          ],
+        )?;
+      }
+      let mint_fees_info = next_account_info(account_info_iter)?;
+      let mut mint_fees = load_or_create_referrer_mint_fees(
+        program_id,
+        voter_info,
+        mint_fees_info,
+        system_program,
+        referrer,
+        collateral.mint.key,
+      )?;
+      let referrer_collateral = CollateralInfos {
+        token_account: collateral.token_account,
+        mint: collateral.mint,
+        vault: referrer_vault_info,
+        token_program: collateral.token_program,
+      };
+      let received = if fee > 0 {
+        deposit_collateral(
+          voter_info,
+          referrer_account_info,
+          Some(&referrer_collateral),
+          fee,
+        )?
+      } else {
+        0
+      };
+      mint_fees.total_fees = math::add(mint_fees.total_fees, received)?;
+      borsh::BorshSerialize::serialize(&mint_fees, &mut &mut mint_fees_info.data.borrow_mut()[..])?;
+      received
+    }
+    None if fee > 0 => {
+      let received = deposit_collateral(voter_info, referrer_account_info, None, fee)?;
+      referrer_account.total_fees = math::add(referrer_account.total_fees, received)?;
+      received
+    }
+    None => 0,
+  };
+  referrer_account.num_referrals = math::add(referrer_account.num_referrals, 1)?;
+
+  borsh::BorshSerialize::serialize(
+    &referrer_account,
+    &mut &mut referrer_account_info.data.borrow_mut()[..],
+  )?;
+
+  msg!("referral: referrer={},fee={}", referrer, received);
+
+  Ok(())
+}
+
+fn load_or_create_referrer_mint_fees<'a>(
+  program_id: &Pubkey,
+  payer_info: &AccountInfo<'a>,
+  mint_fees_info: &AccountInfo<'a>,
+  system_program: &AccountInfo<'a>,
+  referrer: &Pubkey,
+  mint: &Pubkey,
+) -> Result<ReferrerMintFees, ProgramError> {
+  let (mint_fees_pda, mint_fees_bump) = ReferrerMintFees::find_address(program_id, referrer, mint);
+  if !mint_fees_info.key.eq(&mint_fees_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  if !mint_fees_info.data_is_empty() {
+    assert_market_owner(mint_fees_info.owner)?;
+    return Ok(borsh::BorshDeserialize::deserialize(
+      &mut mint_fees_info.data.borrow().as_ref(),
+    )?);
+  }
+  let rent = Rent::get()?;
+  invoke_signed(
+    &system_instruction::create_account(
+      payer_info.key,
+      mint_fees_info.key,
+      rent.minimum_balance(ReferrerMintFees::LEN),
+      u64::try_from(ReferrerMintFees::LEN).expect("data size"),
+      program_id,
+    ),
+    &[
+      payer_info.clone(),
+      mint_fees_info.clone(),
+      system_program.clone(),
+    ],
+    &[&[
+      ReferrerMintFees::SEED_PREFIX.as_bytes(),
+      referrer.as_ref(),
+      mint.as_ref(),
+      &[mint_fees_bump],
+    ]],
+  )?;
+  Ok(ReferrerMintFees::with_params(
+    mint_fees_bump,
+    *referrer,
+    *mint,
+  ))
+}
+
+/// Pays the referral fees held for the signing referrer to it. Lamport withdrawals leave the
+/// referrer account its rent; token withdrawals empty the referrer vault of the mint.
+fn process_withdraw_referral_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let referrer_info = next_account_info(account_info_iter)?;
+  let referrer_account_info = next_account_info(account_info_iter)?;
+  if !referrer_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let (referrer_account_pda, _) = ReferrerAccount::find_address(program_id, referrer_info.key);
+  if !referrer_account_info.key.eq(&referrer_account_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  assert_market_owner(referrer_account_info.owner)?;
+  let mut referrer_account: ReferrerAccount =
+    borsh::BorshDeserialize::deserialize(&mut referrer_account_info.data.borrow().as_ref())?;
+
+  match account_info_iter.next() {
+    Some(token_account) => {
+      let mint_info = next_account_info(account_info_iter)?;
+      let vault = next_account_info(account_info_iter)?;
+      let token_program = next_account_info(account_info_iter)?;
+      assert_token_program(token_program.key)?;
+      if !mint_info.owner.eq(token_program.key) {
+        return Err(HitMyBetError::InvalidCollateral.into());
+      }
+      let (vault_pda, _) =
+        ReferrerAccount::find_vault_address(program_id, referrer_info.key, mint_info.key);
+      if !vault.key.eq(&vault_pda) {
+        return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+      }
+      let mint_fees_info = next_account_info(account_info_iter)?;
+      let (mint_fees_pda, _) =
+        ReferrerMintFees::find_address(program_id, referrer_info.key, mint_info.key);
+      if !mint_fees_info.key.eq(&mint_fees_pda) {
+        return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+      }
+      assert_market_owner(mint_fees_info.owner)?;
+      let mut mint_fees: ReferrerMintFees =
+        borsh::BorshDeserialize::deserialize(&mut mint_fees_info.data.borrow().as_ref())?;
+      let amount = token_account_amount(vault)?;
+      mint_fees.withdrawn_fees = math::add(mint_fees.withdrawn_fees, amount)?;
+      borsh::BorshSerialize::serialize(&mint_fees, &mut &mut mint_fees_info.data.borrow_mut()[..])?;
+      invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+          token_program.key,
+          vault.key,
+          mint_info.key,
+          token_account.key,
+          referrer_account_info.key,
+          &[],
+          amount,
+          mint_decimals(mint_info)?,
+        )?,
+        &[
+          vault.clone(),
+          mint_info.clone(),
+          token_account.clone(),
+          referrer_account_info.clone(),
+          token_program.clone(),
+        ],
+        &[&[
+          ReferrerAccount::SEED_PREFIX.as_bytes(),
+          referrer_info.key.as_ref(),
+          &[referrer_account.bump_seed],
+        ]],
+      )?;
       msg!(
-        "bet={},balance={},num={},votes={}",
-        bet_amount,
-        market.balance_no,
-        market.num_no,
-        prediction.num_votes_no
+        "referral withdrawal: mint={},amount={}",
+        mint_info.key,
+        amount
       );
     }
+    None => {
+      let rent = Rent::get()?.minimum_balance(ReferrerAccount::LEN);
+      let amount = math::sub(referrer_account_info.lamports(), rent)?;
+      referrer_account.withdrawn_fees = math::add(referrer_account.withdrawn_fees, amount)?;
+      borsh::BorshSerialize::serialize(
+        &referrer_account,
+        &mut &mut referrer_account_info.data.borrow_mut()[..],
+      )?;
+      let referrer_starting_lamports = referrer_info.lamports();
+      **referrer_account_info.lamports.borrow_mut() = rent;
+      **referrer_info.lamports.borrow_mut() = math::add(referrer_starting_lamports, amount)?;
+      msg!("referral withdrawal: lamports={}", amount);
+    }
   }
 
-  borsh::BorshSerialize::serialize(
+  Ok(())
+}
+
+/// Deposits one `vote_price` for each set and mints one YES and one NO share per set. Exactly one
+/// of the two shares pays `vote_price` on a market maker market, so sets stay out of the curve and
+/// are only added to the outstanding shares. Parimutuel pools pay no fixed amount per share and
+/// take no sets.
+fn process_mint_complete_set(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  num_sets: u64,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let user_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let system_program = next_account_info(account_info_iter)?;
+  if !user_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_market_owner(prediction_market_info.owner)?;
+  assert_system_program(system_program.key)?;
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  if market.kind != MarketKind::Binary {
+    return Err(HitMyBetError::InvalidMarketKind.into());
+  }
+  if market.pricing == MarketPricing::Parimutuel {
+    return Err(HitMyBetError::InvalidPricingMode.into());
+  }
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+  let clock = solana_program::clock::Clock::get()?;
+  if clock.unix_timestamp >= market.open_until {
+    return Err(HitMyBetError::MarketIsClosed.into());
+  }
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
     &market,
-    &mut &mut prediction_market_info.data.borrow_mut()[..],
   )?;
+  let [yes_shares, no_shares] =
+    next_complete_set_infos(program_id, account_info_iter, prediction_market_info)?;
+
+  let set_cost = math::mul(num_sets, market.vote_price)?;
+  assert_bet_limits(&market, set_cost)?;
+  let deposit_amount = deposit_collateral(
+    user_info,
+    prediction_market_info,
+    collateral_infos.as_ref(),
+    set_cost,
+  )?;
+  market.num_yes = math::add(market.num_yes, num_sets)?;
+  market.num_no = math::add(market.num_no, num_sets)?;
 
   borsh::BorshSerialize::serialize(
-    &prediction,
-    &mut &mut user_prediction_info.data.borrow_mut()[..],
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
   )?;
 
+  mint_shares(&market, prediction_market_info, &yes_shares, num_sets)?;
+  mint_shares(&market, prediction_market_info, &no_shares, num_sets)?;
+
+  msg!("minted sets={},deposit={}", num_sets, deposit_amount);
+
   Ok(())
 }
 
-fn process_resolve_market(accounts: &[AccountInfo], resolution: MarketResolution) -> ProgramResult {
+/// Burns one YES and one NO share per set and returns the `vote_price` paid for each. Only
+/// possible before the market is resolved; afterwards the winning share is claimed for the same
+/// amount.
+fn process_redeem_complete_set(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  num_sets: u64,
+) -> ProgramResult {
   let account_info_iter = &mut accounts.iter();
-  let resolver_info = next_account_info(account_info_iter)?;
+  let user_info = next_account_info(account_info_iter)?;
   let prediction_market_info = next_account_info(account_info_iter)?;
-  assert_market_owner(prediction_market_info.owner)?;
-
-  if !resolver_info.is_signer {
+  if !user_info.is_signer {
     return Err(HitMyBetError::InvalidSigner.into());
   }
+  assert_market_owner(prediction_market_info.owner)?;
 
   let mut market: PredictionMarket =
     borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
 
-  if !market.resolver.eq(&resolver_info.key) {
-    return Err(HitMyBetError::InvalidResolver.into());
+  if market.kind != MarketKind::Binary {
+    return Err(HitMyBetError::InvalidMarketKind.into());
+  }
+  if market.pricing == MarketPricing::Parimutuel {
+    return Err(HitMyBetError::InvalidPricingMode.into());
   }
-
   if market.resolution != MarketResolution::Unresolved {
     return Err(HitMyBetError::MarketIsResolved.into());
   }
 
-  market.resolution = resolution;
-  let clock = solana_program::clock::Clock::get()?;
-  market.open_until = clock.unix_timestamp;
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  let [yes_shares, no_shares] =
+    next_complete_set_infos(program_id, account_info_iter, prediction_market_info)?;
+
+  burn_shares(user_info, &yes_shares, num_sets)?;
+  burn_shares(user_info, &no_shares, num_sets)?;
+
+  market.num_yes = math::sub(market.num_yes, num_sets)?;
+  market.num_no = math::sub(market.num_no, num_sets)?;
 
   borsh::BorshSerialize::serialize(
     &market,
     &mut &mut prediction_market_info.data.borrow_mut()[..],
   )?;
 
-  msg!("resolution: {:?}", market.resolution);
+  let payout = math::mul(num_sets, market.vote_price)?;
+  pay_out(
+    &market,
+    prediction_market_info,
+    user_info,
+    collateral_infos.as_ref(),
+    payout,
+  )?;
+
+  msg!("redeemed sets={},payout={}", num_sets, payout);
 
   Ok(())
 }
 
-fn process_set_market_resolver_admin(accounts: &[AccountInfo]) -> ProgramResult {
+/// Buys `num_shares` outcome shares from the market maker at the quoted cost, which must not
+/// exceed `max_cost`.
+fn process_buy_shares(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  vote: UserVote,
+  num_shares: u64,
+  max_cost: u64,
+) -> ProgramResult {
   let account_info_iter = &mut accounts.iter();
-  let resolver_info = next_account_info(account_info_iter)?;
+  let buyer_info = next_account_info(account_info_iter)?;
   let prediction_market_info = next_account_info(account_info_iter)?;
-  assert_market_owner(prediction_market_info.owner)?;
-
-  if !resolver_info.is_signer {
+  let system_program = next_account_info(account_info_iter)?;
+  if !buyer_info.is_signer {
     return Err(HitMyBetError::InvalidSigner.into());
   }
+  assert_market_owner(prediction_market_info.owner)?;
+  assert_system_program(system_program.key)?;
 
   let mut market: PredictionMarket =
     borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
 
-  market.resolver = resolver_info.key.clone();
+  if market.pricing == MarketPricing::Parimutuel {
+    return Err(HitMyBetError::InvalidPricingMode.into());
+  }
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+  let clock = solana_program::clock::Clock::get()?;
+  if clock.unix_timestamp >= market.open_until {
+    return Err(HitMyBetError::MarketIsClosed.into());
+  }
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  let shares = next_share_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?
+  .ok_or(ProgramError::NotEnoughAccountKeys)?;
+  if vote.side() != Some(shares.side) {
+    return Err(HitMyBetError::InvalidShareMint.into());
+  }
+
+  let before = (market.num_yes, market.num_no);
+  let after = match vote {
+    UserVote::Yes => (before.0.checked_add(num_shares), Some(before.1)),
+    UserVote::No => (Some(before.0), before.1.checked_add(num_shares)),
+    _ => return Err(HitMyBetError::InvalidMarketKind.into()),
+  };
+  let (Some(num_yes), Some(num_no)) = after else {
+    return Err(HitMyBetError::MathOverflow.into());
+  };
+  let cost = match market.pricing {
+    MarketPricing::Lmsr { liquidity } => {
+      lmsr::buy_cost(before, (num_yes, num_no), liquidity, market.vote_price)
+    }
+    _ => {
+      if market.lp_supply == 0 {
+        return Err(HitMyBetError::InsufficientLiquidity.into());
+      }
+      let payout = math::mul(num_shares, market.vote_price)?;
+      cpmm::buy(
+        (market.reserve_yes, market.reserve_no),
+        matches!(vote, UserVote::Yes),
+        payout,
+      )
+      .map(|(cost, (reserve_yes, reserve_no))| {
+        market.reserve_yes = reserve_yes;
+        market.reserve_no = reserve_no;
+        cost
+      })
+    }
+  }
+  .ok_or(HitMyBetError::MathOverflow)?;
+  if cost > max_cost {
+    msg!("cost={},max_cost={}", cost, max_cost);
+    return Err(HitMyBetError::SlippageExceeded.into());
+  }
+  assert_bet_limits(&market, cost)?;
+
+  deposit_collateral(
+    buyer_info,
+    prediction_market_info,
+    collateral_infos.as_ref(),
+    cost,
+  )?;
+  market.num_yes = num_yes;
+  market.num_no = num_no;
 
   borsh::BorshSerialize::serialize(
     &market,
     &mut &mut prediction_market_info.data.borrow_mut()[..],
   )?;
 
-  msg!("resolver updated");
+  mint_shares(&market, prediction_market_info, &shares, num_shares)?;
+
+  msg!(
+    "bought shares={},cost={},yes={},no={}",
+    num_shares,
+    cost,
+    market.num_yes,
+    market.num_no
+  );
 
   Ok(())
 }
 
-fn process_resolve_market_admin(
+/// Sells `num_shares` outcome shares back to the market maker for the quoted return, which must
+/// be at least `min_return`.
+fn process_sell_shares(
+  program_id: &Pubkey,
   accounts: &[AccountInfo],
-  resolution: MarketResolution,
+  vote: UserVote,
+  num_shares: u64,
+  min_return: u64,
 ) -> ProgramResult {
   let account_info_iter = &mut accounts.iter();
-  let resolver_info = next_account_info(account_info_iter)?;
+  let seller_info = next_account_info(account_info_iter)?;
   let prediction_market_info = next_account_info(account_info_iter)?;
-  assert_market_owner(prediction_market_info.owner)?;
-
-  if !resolver_info.is_signer {
+  if !seller_info.is_signer {
     return Err(HitMyBetError::InvalidSigner.into());
   }
+  assert_market_owner(prediction_market_info.owner)?;
 
   let mut market: PredictionMarket =
     borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
 
-  if !crate::ADMIN_RESOLVER.eq(&resolver_info.key) {
-    return Err(HitMyBetError::InvalidResolver.into());
+  if market.pricing == MarketPricing::Parimutuel {
+    return Err(HitMyBetError::InvalidPricingMode.into());
+  }
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
   }
-
-  market.resolution = resolution;
   let clock = solana_program::clock::Clock::get()?;
-  market.open_until = clock.unix_timestamp;
+  if clock.unix_timestamp >= market.open_until {
+    return Err(HitMyBetError::MarketIsClosed.into());
+  }
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  let shares = next_share_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?
+  .ok_or(ProgramError::NotEnoughAccountKeys)?;
+  if vote.side() != Some(shares.side) {
+    return Err(HitMyBetError::InvalidShareMint.into());
+  }
+
+  let proceeds = quote_sell(&mut market, &vote, num_shares)?;
+  if proceeds < min_return {
+    msg!("return={},min_return={}", proceeds, min_return);
+    return Err(HitMyBetError::SlippageExceeded.into());
+  }
+
+  burn_shares(seller_info, &shares, num_shares)?;
 
   borsh::BorshSerialize::serialize(
     &market,
     &mut &mut prediction_market_info.data.borrow_mut()[..],
   )?;
 
-  msg!("resolution: {:?}", market.resolution);
+  pay_out(
+    &market,
+    prediction_market_info,
+    seller_info,
+    collateral_infos.as_ref(),
+    proceeds,
+  )?;
+
+  msg!(
+    "sold shares={},return={},yes={},no={}",
+    num_shares,
+    proceeds,
+    market.num_yes,
+    market.num_no
+  );
 
   Ok(())
 }
 
-fn process_claim_market(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+/// Prices selling `num_shares` of `vote` back to the market maker and takes them out of the
+/// outstanding shares and reserves of `market`.
+fn quote_sell(
+  market: &mut PredictionMarket,
+  vote: &UserVote,
+  num_shares: u64,
+) -> Result<u64, ProgramError> {
+  let before = (market.num_yes, market.num_no);
+  let after = match vote {
+    UserVote::Yes => (before.0.checked_sub(num_shares), Some(before.1)),
+    UserVote::No => (Some(before.0), before.1.checked_sub(num_shares)),
+    _ => return Err(HitMyBetError::InvalidMarketKind.into()),
+  };
+  let (Some(num_yes), Some(num_no)) = after else {
+    return Err(HitMyBetError::MathOverflow.into());
+  };
+  let proceeds = match market.pricing {
+    MarketPricing::Parimutuel => return Err(HitMyBetError::InvalidPricingMode.into()),
+    MarketPricing::Lmsr { liquidity } => {
+      lmsr::sell_return(before, (num_yes, num_no), liquidity, market.vote_price)
+    }
+    MarketPricing::Cpmm => {
+      let payout = math::mul(num_shares, market.vote_price)?;
+      cpmm::sell(
+        (market.reserve_yes, market.reserve_no),
+        matches!(vote, UserVote::Yes),
+        payout,
+      )
+      .map(|(proceeds, (reserve_yes, reserve_no))| {
+        market.reserve_yes = reserve_yes;
+        market.reserve_no = reserve_no;
+        proceeds
+      })
+    }
+  }
+  .ok_or(HitMyBetError::MathOverflow)?;
+  market.num_yes = num_yes;
+  market.num_no = num_no;
+
+  Ok(proceeds)
+}
+
+/// Sells `num_votes` recorded in the voter's UserPrediction back to the market before it closes.
+/// Parimutuel votes are refunded their stake minus [`PredictionMarket::EXIT_FEE_BPS`], which stays
+/// in the pool, so later sellers do not collect the exit fees of earlier ones. The refund never
+/// exceeds the votes' share of the side pool, which is smaller when a transfer fee was taken from
+/// the bets. Market maker votes are sold at the curve price.
+fn process_sell_prediction(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  vote: UserVote,
+  num_votes: u64,
+) -> ProgramResult {
   let account_info_iter = &mut accounts.iter();
-  let claimer_info = next_account_info(account_info_iter)?;
+  let voter_info = next_account_info(account_info_iter)?;
   let prediction_market_info = next_account_info(account_info_iter)?;
   let user_prediction_info = next_account_info(account_info_iter)?;
-  assert_market_owner(prediction_market_info.owner)?;
-  if !claimer_info.is_signer {
+  if !voter_info.is_signer {
     return Err(HitMyBetError::InvalidSigner.into());
   }
+  assert_market_owner(prediction_market_info.owner)?;
 
   let (user_prediction_pda, _) = Pubkey::find_program_address(
     &[
       UserPrediction::SEED_PREFIX.as_bytes(),
       prediction_market_info.key.as_ref(),
-      claimer_info.key.as_ref(),
+      voter_info.key.as_ref(),
     ],
     program_id,
   );
   if !user_prediction_info.key.eq(&user_prediction_pda) {
     return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
   }
+  if user_prediction_info.data_is_empty() {
+    return Err(HitMyBetError::InsufficientVotes.into());
+  }
 
-  let market: PredictionMarket =
+  let mut market: PredictionMarket =
     borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+  let mut prediction: UserPrediction =
+    borsh::BorshDeserialize::deserialize(&mut user_prediction_info.data.borrow().as_ref())?;
 
-  if market.resolution == MarketResolution::Unresolved {
-    return Err(HitMyBetError::MarketIsNotResolved.into());
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+  let clock = solana_program::clock::Clock::get()?;
+  if clock.unix_timestamp >= market.open_until {
+    return Err(HitMyBetError::MarketIsClosed.into());
   }
 
-  let prediction: UserPrediction =
-    borsh::BorshDeserialize::deserialize(&mut user_prediction_info.data.borrow().as_ref())?;
-
-  let win_per_vote: u64;
-  let votes_to_claim: u64;
-  match market.resolution {
-    MarketResolution::Unresolved => {
-      return Err(HitMyBetError::MarketIsNotResolved.into());
-    }
-    MarketResolution::Yes => {
-      win_per_vote = market.balance_no / market.num_yes;
-      votes_to_claim = prediction.num_votes_yes;
-    }
-    MarketResolution::No => {
-      win_per_vote = market.balance_yes / market.num_no;
-      votes_to_claim = prediction.num_votes_no;
-    }
-    MarketResolution::Tie => {
-      win_per_vote = 0;
-      votes_to_claim = prediction.num_votes_no + prediction.num_votes_yes;
+  let held = match vote {
+    UserVote::Yes => &mut prediction.num_votes_yes,
+    UserVote::No => &mut prediction.num_votes_no,
+    UserVote::Outcome(outcome) if usize::from(outcome) < MAX_OUTCOMES => {
+      &mut prediction.outcome_votes[usize::from(outcome)]
     }
+    UserVote::Outcome(_) => return Err(HitMyBetError::InvalidOutcome.into()),
+    UserVote::Unspecified => return Err(HitMyBetError::InstructionUnpackError.into()),
   };
+  if *held < num_votes {
+    return Err(HitMyBetError::InsufficientVotes.into());
+  }
+  *held = math::sub(*held, num_votes)?;
 
-  if votes_to_claim == 0 {
-    msg!("lost prediction: {:?}", prediction);
-
-    let dest_starting_lamports = claimer_info.lamports();
-    **claimer_info.lamports.borrow_mut() = dest_starting_lamports
-      .checked_add(user_prediction_info.lamports())
-      .unwrap();
-    **user_prediction_info.lamports.borrow_mut() = 0;
-
-    user_prediction_info.assign(&solana_program::system_program::ID);
-    user_prediction_info.realloc(0, false)?;
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
 
-    return Ok(());
+  let refund = if market.pricing == MarketPricing::Parimutuel {
+    let (balance, total_votes) = match vote {
+      UserVote::Yes => (&mut market.balance_yes, &mut market.num_yes),
+      UserVote::No => (&mut market.balance_no, &mut market.num_no),
+      UserVote::Outcome(outcome) => {
+        let outcome = usize::from(outcome);
+        (
+          &mut market.outcome_balances[outcome],
+          &mut market.outcome_votes[outcome],
+        )
+      }
+      UserVote::Unspecified => unreachable!(),
+    };
+    let stake = math::mul(num_votes, market.vote_price)?;
+    let refund = math::sub(stake, math::bps(stake, PredictionMarket::EXIT_FEE_BPS)?)?
+      .min(math::pool_share(*balance, num_votes, *total_votes)?);
+    *balance = math::sub(*balance, refund)?;
+    *total_votes = math::sub(*total_votes, num_votes)?;
+    refund
+  } else {
+    quote_sell(&mut market, &vote, num_votes)?
+  };
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+  borsh::BorshSerialize::serialize(
+    &prediction,
+    &mut &mut user_prediction_info.data.borrow_mut()[..],
+  )?;
+
+  pay_out(
+    &market,
+    prediction_market_info,
+    voter_info,
+    collateral_infos.as_ref(),
+    refund,
+  )?;
+
+  msg!(
+    "sold prediction votes={},refund={},prediction={:?}",
+    num_votes,
+    refund,
+    prediction
+  );
+
+  Ok(())
+}
+
+/// Deposits `amount` into the pool of a constant-product market. The deposit is added to the
+/// reserves in their current ratio, and the exposure of the side that does not fit is kept in the
+/// provider's LiquidityPosition.
+fn process_add_liquidity(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  amount: u64,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let provider_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let position_info = next_account_info(account_info_iter)?;
+  let system_program = next_account_info(account_info_iter)?;
+  if !provider_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_market_owner(prediction_market_info.owner)?;
+  assert_system_program(system_program.key)?;
+
+  let (position_pda, position_bump) =
+    LiquidityPosition::find_address(program_id, prediction_market_info.key, provider_info.key);
+  if !position_info.key.eq(&position_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  if market.pricing != MarketPricing::Cpmm {
+    return Err(HitMyBetError::InvalidPricingMode.into());
+  }
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+  let clock = solana_program::clock::Clock::get()?;
+  if clock.unix_timestamp >= market.open_until {
+    return Err(HitMyBetError::MarketIsClosed.into());
+  }
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  let deposit = deposit_collateral(
+    provider_info,
+    prediction_market_info,
+    collateral_infos.as_ref(),
+    amount,
+  )?;
+  if deposit == 0 {
+    return Err(HitMyBetError::InsufficientLiquidity.into());
+  }
+
+  let mut position: LiquidityPosition;
+  if position_info.data_is_empty() {
+    position = LiquidityPosition::with_seed(position_bump);
+    market.open_positions = math::add(market.open_positions, 1)?;
+
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(LiquidityPosition::LEN);
+
+    invoke_signed(
+      &system_instruction::create_account(
+        provider_info.key,
+        position_info.key,
+        rent_lamports,
+        u64::try_from(LiquidityPosition::LEN).expect("data size"),
+        program_id,
+      ),
+      &[
+        provider_info.clone(),
+        position_info.clone(),
+        system_program.clone(),
+      ],
+      &[&[
+        LiquidityPosition::SEED_PREFIX.as_bytes(),
+        prediction_market_info.key.as_ref(),
+        provider_info.key.as_ref(),
+        &[position_bump],
+      ]],
+    )?;
+  } else {
+    position = borsh::BorshDeserialize::deserialize(&mut position_info.data.borrow().as_ref())?;
+  }
+
+  let minted = if market.lp_supply == 0 {
+    market.reserve_yes = deposit;
+    market.reserve_no = deposit;
+    deposit
+  } else {
+    let max_reserve = market.reserve_yes.max(market.reserve_no);
+    let add_yes = math::pool_share(market.reserve_yes, deposit, max_reserve)?;
+    let add_no = math::pool_share(market.reserve_no, deposit, max_reserve)?;
+    market.reserve_yes = math::add(market.reserve_yes, add_yes)?;
+    market.reserve_no = math::add(market.reserve_no, add_no)?;
+    position.excess_yes = math::add(position.excess_yes, math::sub(deposit, add_yes)?)?;
+    position.excess_no = math::add(position.excess_no, math::sub(deposit, add_no)?)?;
+    math::pool_share(market.lp_supply, deposit, max_reserve)?
+  };
+  market.lp_supply = math::add(market.lp_supply, minted)?;
+  position.lp_shares = math::add(position.lp_shares, minted)?;
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+  borsh::BorshSerialize::serialize(&position, &mut &mut position_info.data.borrow_mut()[..])?;
+
+  msg!(
+    "added liquidity={},lp_shares={},reserves=({},{})",
+    deposit,
+    minted,
+    market.reserve_yes,
+    market.reserve_no
+  );
+
+  Ok(())
+}
+
+/// Takes `lp_shares` worth of both reserves out of the pool and pays out the complete sets they
+/// form. Whatever exposure is left on one side stays in the provider's LiquidityPosition.
+fn process_remove_liquidity(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  lp_shares: u64,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let provider_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let position_info = next_account_info(account_info_iter)?;
+  if !provider_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_market_owner(prediction_market_info.owner)?;
+
+  let (position_pda, _) =
+    LiquidityPosition::find_address(program_id, prediction_market_info.key, provider_info.key);
+  if !position_info.key.eq(&position_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+  let mut position: LiquidityPosition =
+    borsh::BorshDeserialize::deserialize(&mut position_info.data.borrow().as_ref())?;
+
+  if market.pricing != MarketPricing::Cpmm {
+    return Err(HitMyBetError::InvalidPricingMode.into());
+  }
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+  if lp_shares > position.lp_shares {
+    return Err(ProgramError::InsufficientFunds);
+  }
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+
+  let remove_yes = math::pool_share(market.reserve_yes, lp_shares, market.lp_supply)?;
+  let remove_no = math::pool_share(market.reserve_no, lp_shares, market.lp_supply)?;
+  market.reserve_yes = math::sub(market.reserve_yes, remove_yes)?;
+  market.reserve_no = math::sub(market.reserve_no, remove_no)?;
+  market.lp_supply = math::sub(market.lp_supply, lp_shares)?;
+  position.lp_shares = math::sub(position.lp_shares, lp_shares)?;
+
+  let exposure_yes = math::add(position.excess_yes, remove_yes)?;
+  let exposure_no = math::add(position.excess_no, remove_no)?;
+  let amount = exposure_yes.min(exposure_no);
+  position.excess_yes = math::sub(exposure_yes, amount)?;
+  position.excess_no = math::sub(exposure_no, amount)?;
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+  borsh::BorshSerialize::serialize(&position, &mut &mut position_info.data.borrow_mut()[..])?;
+
+  pay_out(
+    &market,
+    prediction_market_info,
+    provider_info,
+    collateral_infos.as_ref(),
+    amount,
+  )?;
+
+  msg!(
+    "removed lp_shares={},payout={},reserves=({},{})",
+    lp_shares,
+    amount,
+    market.reserve_yes,
+    market.reserve_no
+  );
+
+  Ok(())
+}
+
+/// Places a limit order for YES shares of a binary market in the market order book, creating the
+/// book on first use. Bids escrow `price * quantity` of collateral in the market and asks burn
+/// the offered shares until the order is filled or cancelled.
+fn process_place_order(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  side: OrderSide,
+  price: u64,
+  quantity: u64,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let owner_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let order_book_info = next_account_info(account_info_iter)?;
+  let system_program = next_account_info(account_info_iter)?;
+  if !owner_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_market_owner(prediction_market_info.owner)?;
+  assert_system_program(system_program.key)?;
+
+  let (order_book_pda, order_book_bump) =
+    OrderBook::find_address(program_id, prediction_market_info.key);
+  if !order_book_info.key.eq(&order_book_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  let market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  if market.kind != MarketKind::Binary {
+    return Err(HitMyBetError::InvalidMarketKind.into());
+  }
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+  let clock = solana_program::clock::Clock::get()?;
+  if clock.unix_timestamp >= market.open_until {
+    return Err(HitMyBetError::MarketIsClosed.into());
+  }
+  if price == 0 || quantity == 0 {
+    return Err(HitMyBetError::InvalidOrder.into());
+  }
+  // Bids and asks are bounded by their notional, like bets.
+  let escrow = math::mul(price, quantity)?;
+  assert_bet_limits(&market, escrow)?;
+
+  let mut order_book: OrderBook;
+  if order_book_info.data_is_empty() {
+    order_book = OrderBook::with_seed(order_book_bump);
+
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(OrderBook::LEN);
+
+    invoke_signed(
+      &system_instruction::create_account(
+        owner_info.key,
+        order_book_info.key,
+        rent_lamports,
+        u64::try_from(OrderBook::LEN).expect("data size"),
+        program_id,
+      ),
+      &[
+        owner_info.clone(),
+        order_book_info.clone(),
+        system_program.clone(),
+      ],
+      &[&[
+        OrderBook::SEED_PREFIX.as_bytes(),
+        prediction_market_info.key.as_ref(),
+        &[order_book_bump],
+      ]],
+    )?;
+  } else {
+    order_book = borsh::BorshDeserialize::deserialize(&mut order_book_info.data.borrow().as_ref())?;
+  }
+  if order_book.orders.len() >= MAX_ORDERS {
+    return Err(HitMyBetError::OrderBookFull.into());
+  }
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  match side {
+    // Escrowed bids are paid to the seller in full, so fee-bearing mints cannot back them.
+    OrderSide::Bid => {
+      if let Some(collateral) = &collateral_infos {
+        if mint_has_transfer_fee(collateral.mint)? {
+          return Err(HitMyBetError::UnsupportedMint.into());
+        }
+      }
+      deposit_collateral(
+        owner_info,
+        prediction_market_info,
+        collateral_infos.as_ref(),
+        escrow,
+      )?;
+    }
+    OrderSide::Ask => {
+      let shares = next_yes_share_infos(
+        program_id,
+        account_info_iter,
+        prediction_market_info,
+        &market,
+      )?;
+      burn_shares(owner_info, &shares, quantity)?;
+    }
+  }
+
+  let order = Order {
+    order_id: order_book.next_order_id,
+    owner: *owner_info.key,
+    side,
+    price,
+    quantity,
+  };
+  order_book.next_order_id = math::add(order_book.next_order_id, 1)?;
+  order_book.orders.push(order);
+
+  borsh::BorshSerialize::serialize(&order_book, &mut &mut order_book_info.data.borrow_mut()[..])?;
+
+  msg!("placed order: {:?}", order);
+
+  Ok(())
+}
+
+/// Cancels an open order and returns its escrow to the order owner: collateral for bids,
+/// re-minted shares for asks. Owners can cancel their orders at any time, also after the market
+/// closes or resolves. Once the resolution is final anyone can cancel the orders left on the
+/// book, so that they cannot keep the market from closing.
+fn process_cancel_order(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  order_id: u64,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let signer_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let order_book_info = next_account_info(account_info_iter)?;
+  let owner_info = next_account_info(account_info_iter)?;
+  if !signer_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_market_owner(prediction_market_info.owner)?;
+
+  let market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+  let mut order_book = load_order_book(program_id, prediction_market_info, order_book_info)?;
+
+  let is_owner = signer_info.key.eq(owner_info.key);
+  if !is_owner {
+    assert_resolution_final(&market)?;
+  }
+  let index = order_book
+    .orders
+    .iter()
+    .position(|order| order.order_id == order_id && order.owner.eq(owner_info.key))
+    .ok_or(HitMyBetError::OrderNotFound)?;
+  let order = order_book.orders.remove(index);
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  if !is_owner {
+    if let Some(collateral) = &collateral_infos {
+      if order.side == OrderSide::Bid
+        && !token_account_owner(collateral.token_account)?.eq(&order.owner)
+      {
+        return Err(HitMyBetError::InvalidOrder.into());
+      }
+    }
+  }
+  match order.side {
+    OrderSide::Bid => pay_out(
+      &market,
+      prediction_market_info,
+      owner_info,
+      collateral_infos.as_ref(),
+      math::mul(order.price, order.quantity)?,
+    )?,
+    OrderSide::Ask => {
+      let shares = next_yes_share_infos(
+        program_id,
+        account_info_iter,
+        prediction_market_info,
+        &market,
+      )?;
+      if !is_owner && !token_account_owner(shares.token_account)?.eq(&order.owner) {
+        return Err(HitMyBetError::InvalidOrder.into());
+      }
+      mint_shares(&market, prediction_market_info, &shares, order.quantity)?;
+    }
+  }
+
+  borsh::BorshSerialize::serialize(&order_book, &mut &mut order_book_info.data.borrow_mut()[..])?;
+
+  msg!("cancelled order: {:?}", order);
+
+  Ok(())
+}
+
+/// Crank: fills the best bid against the best ask when they cross. The fill executes at the bid
+/// price, so the bid escrow of the filled quantity goes to the seller and the buyer is minted the
+/// shares.
+fn process_match_orders(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let cranker_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let order_book_info = next_account_info(account_info_iter)?;
+  let seller_info = next_account_info(account_info_iter)?;
+  if !cranker_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_market_owner(prediction_market_info.owner)?;
+
+  let market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+  let mut order_book = load_order_book(program_id, prediction_market_info, order_book_info)?;
+
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+  let clock = solana_program::clock::Clock::get()?;
+  if clock.unix_timestamp >= market.open_until {
+    return Err(HitMyBetError::MarketIsClosed.into());
+  }
+
+  let (Some(bid_index), Some(ask_index)) = (order_book.best_bid(), order_book.best_ask()) else {
+    return Err(HitMyBetError::OrdersNotCrossed.into());
+  };
+  let bid = order_book.orders[bid_index];
+  let ask = order_book.orders[ask_index];
+  if bid.price < ask.price {
+    return Err(HitMyBetError::OrdersNotCrossed.into());
+  }
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  let shares = next_yes_share_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  let seller_account = match &collateral_infos {
+    Some(collateral) => token_account_owner(collateral.token_account)?,
+    None => *seller_info.key,
+  };
+  if !seller_info.key.eq(&ask.owner)
+    || !seller_account.eq(&ask.owner)
+    || !token_account_owner(shares.token_account)?.eq(&bid.owner)
+  {
+    return Err(HitMyBetError::InvalidOrder.into());
+  }
+
+  let quantity = bid.quantity.min(ask.quantity);
+  mint_shares(&market, prediction_market_info, &shares, quantity)?;
+  pay_out(
+    &market,
+    prediction_market_info,
+    seller_info,
+    collateral_infos.as_ref(),
+    math::mul(quantity, bid.price)?,
+  )?;
+
+  order_book.orders[bid_index].quantity =
+    math::sub(order_book.orders[bid_index].quantity, quantity)?;
+  order_book.orders[ask_index].quantity =
+    math::sub(order_book.orders[ask_index].quantity, quantity)?;
+  order_book.orders.retain(|order| order.quantity > 0);
+
+  borsh::BorshSerialize::serialize(&order_book, &mut &mut order_book_info.data.borrow_mut()[..])?;
+
+  msg!(
+    "fill: bid={},ask={},price={},quantity={}",
+    bid.order_id,
+    ask.order_id,
+    bid.price,
+    quantity
+  );
+
+  Ok(())
+}
+
+fn load_order_book(
+  program_id: &Pubkey,
+  prediction_market_info: &AccountInfo,
+  order_book_info: &AccountInfo,
+) -> Result<OrderBook, ProgramError> {
+  let (order_book_pda, _) = OrderBook::find_address(program_id, prediction_market_info.key);
+  if !order_book_info.key.eq(&order_book_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  if order_book_info.data_is_empty() {
+    return Err(HitMyBetError::OrderNotFound.into());
+  }
+  Ok(borsh::BorshDeserialize::deserialize(
+    &mut order_book_info.data.borrow().as_ref(),
+  )?)
+}
+
+/// Resolves the market as its resolver or, on markets with a resolution bond, as anyone posting
+/// the bond once betting has closed.
+fn process_resolve_market(accounts: &[AccountInfo], resolution: MarketResolution) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let resolver_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  assert_market_owner(prediction_market_info.owner)?;
+
+  if !resolver_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  // Markets with a resolver set or an oracle only resolve through `SubmitResolutionVote` or
+  // `ResolveFromOracle`.
+  if market.has_resolver_set || market.oracle.is_some() {
+    return Err(HitMyBetError::InvalidResolver.into());
+  }
+
+  let is_resolver = market.resolver.eq(resolver_info.key);
+  if !is_resolver && market.resolution_bond == 0 {
+    return Err(HitMyBetError::InvalidResolver.into());
+  }
+
+  assert_resolution_kind(&market, &resolution)?;
+
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+
+  market.proposer = Some(*resolver_info.key);
+  market.proposer_bond = 0;
+  if !is_resolver {
+    let clock = solana_program::clock::Clock::get()?;
+    if clock.unix_timestamp < market.open_until {
+      return Err(HitMyBetError::MarketIsOpen.into());
+    }
+    let system_program = next_account_info(account_info_iter)?;
+    post_resolution_bond(
+      resolver_info,
+      prediction_market_info,
+      system_program,
+      market.resolution_bond,
+    )?;
+    market.proposer_bond = market.resolution_bond;
+  }
+
+  let dispute_period = market.dispute_period;
+  set_resolution(&mut market, resolution, dispute_period)?;
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  msg!(
+    "resolution: {:?},status={:?},dispute_deadline={},bond={}",
+    market.resolution,
+    market.resolution_status,
+    market.dispute_deadline,
+    market.proposer_bond
+  );
+
+  Ok(())
+}
+
+fn post_resolution_bond<'a>(
+  signer_info: &AccountInfo<'a>,
+  prediction_market_info: &AccountInfo<'a>,
+  system_program: &AccountInfo<'a>,
+  bond: u64,
+) -> ProgramResult {
+  assert_system_program(system_program.key)?;
+  invoke(
+    &system_instruction::transfer(signer_info.key, prediction_market_info.key, bond),
+    &[
+      signer_info.clone(),
+      prediction_market_info.clone(),
+      system_program.clone(),
+    ],
+  )
+}
+
+fn resolver_set_is_deadlocked(
+  program_id: &Pubkey,
+  prediction_market_info: &AccountInfo,
+  resolver_set_info: &AccountInfo,
+  market: &PredictionMarket,
+) -> Result<bool, ProgramError> {
+  if !market.has_resolver_set {
+    return Ok(false);
+  }
+  let (resolver_set_pda, _) = ResolverSet::find_address(program_id, prediction_market_info.key);
+  if !resolver_set_info.key.eq(&resolver_set_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  assert_market_owner(resolver_set_info.owner)?;
+  let resolver_set: ResolverSet =
+    borsh::BorshDeserialize::deserialize(&mut resolver_set_info.data.borrow().as_ref())?;
+  Ok(resolver_set.is_deadlocked())
+}
+
+/// Pays out the bonds of a resolution settled by the admin. The side the admin agrees with gets
+/// its bond back and half of the other side's bond, and the treasury takes the rest. A proposal
+/// overturned without a challenger forfeits its whole bond to the treasury.
+fn settle_resolution_bonds<'a, 'b: 'a, I: Iterator<Item = &'a AccountInfo<'b>>>(
+  program_id: &Pubkey,
+  account_info_iter: &mut I,
+  prediction_market_info: &AccountInfo<'b>,
+  market: &mut PredictionMarket,
+  proposal_upheld: bool,
+) -> ProgramResult {
+  let treasury_info = next_account_info(account_info_iter)?;
+  let (treasury_pda, _) = Treasury::find_address(program_id);
+  if !treasury_info.key.eq(&treasury_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  let proposer_info = next_account_info(account_info_iter)?;
+  if market.proposer != Some(*proposer_info.key) {
+    return Err(HitMyBetError::InvalidBondAccount.into());
+  }
+  let challenger_info = if market.challenger_bond > 0 {
+    let challenger_info = next_account_info(account_info_iter)?;
+    if market.challenger != Some(*challenger_info.key) {
+      return Err(HitMyBetError::InvalidBondAccount.into());
+    }
+    Some(challenger_info)
+  } else {
+    None
+  };
+
+  let (winner_info, winner_bond, loser_bond) = if proposal_upheld {
+    (
+      Some(proposer_info),
+      market.proposer_bond,
+      market.challenger_bond,
+    )
+  } else {
+    (
+      challenger_info,
+      market.challenger_bond,
+      market.proposer_bond,
+    )
+  };
+  let reward = if winner_info.is_some() {
+    loser_bond / 2
+  } else {
+    0
+  };
+  let to_treasury = math::sub(loser_bond, reward)?;
+  let to_winner = math::add(winner_bond, reward)?;
+
+  if let Some(winner_info) = winner_info {
+    pay_out(market, prediction_market_info, winner_info, None, to_winner)?;
+  }
+  pay_out(
+    market,
+    prediction_market_info,
+    treasury_info,
+    None,
+    to_treasury,
+  )?;
+  add_treasury_fees(treasury_info, to_treasury)?;
+  market.proposer_bond = 0;
+  market.challenger_bond = 0;
+
+  msg!(
+    "bonds settled: upheld={},winner={},treasury={}",
+    proposal_upheld,
+    to_winner,
+    to_treasury
+  );
+
+  Ok(())
+}
+
+/// Makes an unchallenged resolution final once its dispute period has ended and returns the
+/// proposer's bond.
+fn process_finalize_resolution(accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let proposer_info = next_account_info(account_info_iter)?;
+  assert_market_owner(prediction_market_info.owner)?;
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  assert_resolution_final(&market)?;
+  if market.proposer != Some(*proposer_info.key) {
+    return Err(HitMyBetError::InvalidBondAccount.into());
+  }
+
+  let bond = market.proposer_bond;
+  market.resolution_status = ResolutionStatus::Final;
+  market.proposer_bond = 0;
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  if bond > 0 {
+    pay_out(&market, prediction_market_info, proposer_info, None, bond)?;
+  }
+
+  msg!(
+    "resolution final: {:?},bond returned={}",
+    market.resolution,
+    bond
+  );
+
+  Ok(())
+}
+
+/// Records a vote of a member of the market's resolver set, resolving the market once enough
+/// votes agree.
+fn process_submit_resolution_vote(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  resolution: MarketResolution,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let resolver_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let resolver_set_info = next_account_info(account_info_iter)?;
+  assert_market_owner(prediction_market_info.owner)?;
+  assert_market_owner(resolver_set_info.owner)?;
+
+  if !resolver_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+  if !market.has_resolver_set {
+    return Err(HitMyBetError::InvalidResolver.into());
+  }
+  let (resolver_set_pda, _) = ResolverSet::find_address(program_id, prediction_market_info.key);
+  if !resolver_set_info.key.eq(&resolver_set_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+  assert_resolution_kind(&market, &resolution)?;
+
+  let mut resolver_set: ResolverSet =
+    borsh::BorshDeserialize::deserialize(&mut resolver_set_info.data.borrow().as_ref())?;
+  let index = resolver_set
+    .position(resolver_info.key)
+    .ok_or(HitMyBetError::InvalidResolver)?;
+  if resolver_set.votes[index].is_some() {
+    return Err(HitMyBetError::ResolverAlreadyVoted.into());
+  }
+  if resolver_set.is_deadlocked() {
+    return Err(HitMyBetError::ResolverSetDeadlocked.into());
+  }
+  resolver_set.votes[index] = Some(resolution);
+  msg!("resolver vote: {:?}", resolution);
+
+  if let Some(quorum) = resolver_set.quorum() {
+    market.proposer = None;
+    let dispute_period = market.dispute_period;
+    set_resolution(&mut market, quorum, dispute_period)?;
+    msg!(
+      "resolution: {:?},status={:?},dispute_deadline={}",
+      market.resolution,
+      market.resolution_status,
+      market.dispute_deadline
+    );
+  } else if resolver_set.is_deadlocked() {
+    msg!("resolver set deadlocked, the admin resolves the market");
+  }
+
+  borsh::BorshSerialize::serialize(
+    &resolver_set,
+    &mut &mut resolver_set_info.data.borrow_mut()[..],
+  )?;
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  Ok(())
+}
+
+/// Resolves an oracle market from a price its feed published within [`oracle::MAX_PRICE_AGE`]
+/// after `resolve_at` that is still fresh and confident enough. Markets nobody resolves within
+/// that window are left to `ResolveMarketAdmin`.
+fn process_resolve_from_oracle<F: PriceFeed>(accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let price_feed_info = next_account_info(account_info_iter)?;
+  assert_market_owner(prediction_market_info.owner)?;
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+  let oracle = market.oracle.ok_or(HitMyBetError::InvalidPriceFeed)?;
+  if !price_feed_info.key.eq(&oracle.price_feed) {
+    return Err(HitMyBetError::InvalidPriceFeed.into());
+  }
+
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+
+  let reading = F::read(price_feed_info)?;
+  if reading.feed_id != oracle.feed_id || reading.exponent != oracle.exponent {
+    return Err(HitMyBetError::InvalidPriceFeed.into());
+  }
+  let clock = solana_program::clock::Clock::get()?;
+  if reading.publish_time < oracle.resolve_at
+    || reading.publish_time > oracle.resolve_at.saturating_add(oracle::MAX_PRICE_AGE)
+    || clock.unix_timestamp.saturating_sub(reading.publish_time) > oracle::MAX_PRICE_AGE
+  {
+    msg!(
+      "price published at {},resolve_at={},now={}",
+      reading.publish_time,
+      oracle.resolve_at,
+      clock.unix_timestamp
+    );
+    return Err(HitMyBetError::StalePrice.into());
+  }
+  if !reading.is_confident() {
+    return Err(HitMyBetError::PriceTooUncertain.into());
+  }
+
+  let resolution = if oracle.is_met(reading.price) {
+    MarketResolution::Yes
+  } else {
+    MarketResolution::No
+  };
+  market.proposer = None;
+  let dispute_period = market.dispute_period;
+  set_resolution(&mut market, resolution, dispute_period)?;
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  msg!(
+    "resolution: {:?},price={},conf={},exponent={},status={:?}",
+    market.resolution,
+    reading.price,
+    reading.confidence,
+    reading.exponent,
+    market.resolution_status
+  );
+
+  Ok(())
+}
+
+/// Rejects bets, purchases and orders of `amount` outside the market's [`BetLimits`].
+fn assert_bet_limits(market: &PredictionMarket, amount: u64) -> ProgramResult {
+  if !market.limits().contains(amount) {
+    msg!("bet={},limits={:?}", amount, market.limits());
+    return Err(HitMyBetError::BetOutOfBounds.into());
+  }
+  Ok(())
+}
+
+fn held_bonds(market: &PredictionMarket) -> Result<u64, HitMyBetError> {
+  math::add(market.proposer_bond, market.challenger_bond)
+}
+
+/// Resolves the market now and closes betting. With a `dispute_period` the resolution is proposed
+/// and becomes final when the period ends, otherwise it is final at once. The claim period, if
+/// any, starts when the resolution becomes final.
+fn set_resolution(
+  market: &mut PredictionMarket,
+  resolution: MarketResolution,
+  dispute_period: UnixTimestamp,
+) -> ProgramResult {
+  let clock = solana_program::clock::Clock::get()?;
+  market.resolution = resolution;
+  market.open_until = clock.unix_timestamp;
+  let final_at = if dispute_period > 0 {
+    market.resolution_status = ResolutionStatus::Proposed;
+    market.dispute_deadline = clock
+      .unix_timestamp
+      .checked_add(dispute_period)
+      .ok_or(HitMyBetError::MathOverflow)?;
+    market.dispute_deadline
+  } else {
+    market.resolution_status = ResolutionStatus::Final;
+    clock.unix_timestamp
+  };
+  if market.claim_period > 0 {
+    market.claim_deadline = final_at
+      .checked_add(market.claim_period)
+      .ok_or(HitMyBetError::MathOverflow)?;
+  }
+  Ok(())
+}
+
+/// Fails unless the market is resolved and its resolution can no longer be disputed.
+fn assert_resolution_final(market: &PredictionMarket) -> ProgramResult {
+  if market.resolution == MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsNotResolved.into());
+  }
+  let is_final = match market.resolution_status {
+    ResolutionStatus::Final => true,
+    ResolutionStatus::Proposed => {
+      solana_program::clock::Clock::get()?.unix_timestamp >= market.dispute_deadline
+    }
+    ResolutionStatus::UnderDispute => false,
+  };
+  if !is_final {
+    return Err(HitMyBetError::ResolutionNotFinal.into());
+  }
+  Ok(())
+}
+
+/// Disputes a proposed resolution before its dispute period ends, posting the resolution bond on
+/// bonded markets. Without a bond only market participants can dispute, so that freezing claims
+/// is not free. The market stays under dispute until the program config admin settles it with
+/// `ResolveMarketAdmin`.
+fn process_dispute_resolution(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let disputer_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let system_program = next_account_info(account_info_iter)?;
+  assert_market_owner(prediction_market_info.owner)?;
+
+  if !disputer_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  let clock = solana_program::clock::Clock::get()?;
+  if market.resolution_status != ResolutionStatus::Proposed
+    || clock.unix_timestamp >= market.dispute_deadline
+  {
+    return Err(HitMyBetError::ResolutionNotDisputable.into());
+  }
+
+  // Challengers of bonded markets post the same bond as proposers.
+  if market.resolution_bond > 0 {
+    post_resolution_bond(
+      disputer_info,
+      prediction_market_info,
+      system_program,
+      market.resolution_bond,
+    )?;
+    market.challenger_bond = market.resolution_bond;
+  } else {
+    let position_info = next_account_info(account_info_iter)?;
+    assert_market_participant(
+      program_id,
+      prediction_market_info,
+      &market,
+      disputer_info.key,
+      position_info,
+    )?;
+  }
+  market.challenger = Some(*disputer_info.key);
+  market.resolution_status = ResolutionStatus::UnderDispute;
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  msg!(
+    "resolution disputed: {:?},disputer={}",
+    market.resolution,
+    disputer_info.key
+  );
+
+  Ok(())
+}
+
+/// Checks that `position_info` is a position of `participant` in the market: either its
+/// `UserPrediction` with votes, or its token account holding shares of one of the market's share
+/// mints.
+fn assert_market_participant(
+  program_id: &Pubkey,
+  prediction_market_info: &AccountInfo,
+  market: &PredictionMarket,
+  participant: &Pubkey,
+  position_info: &AccountInfo,
+) -> ProgramResult {
+  let has_position = if position_info.owner.eq(program_id) {
+    let (user_prediction_pda, _) = Pubkey::find_program_address(
+      &[
+        UserPrediction::SEED_PREFIX.as_bytes(),
+        prediction_market_info.key.as_ref(),
+        participant.as_ref(),
+      ],
+      program_id,
+    );
+    if !position_info.key.eq(&user_prediction_pda) {
+      return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+    }
+    let prediction: UserPrediction =
+      borsh::BorshDeserialize::deserialize(&mut position_info.data.borrow().as_ref())?;
+    prediction.num_votes_yes > 0
+      || prediction.num_votes_no > 0
+      || prediction.outcome_votes.iter().any(|votes| *votes > 0)
+  } else if spl_token::check_id(position_info.owner) {
+    let account_data = position_info.data.borrow();
+    let account = StateWithExtensions::<Account>::unpack(&account_data)?.base;
+    account.owner.eq(participant)
+      && account.amount > 0
+      && (0..market.kind.num_sides()).any(|side| {
+        PredictionMarket::find_share_mint_address(program_id, prediction_market_info.key, side)
+          .0
+          .eq(&account.mint)
+      })
+  } else {
+    false
+  };
+  if !has_position {
+    return Err(HitMyBetError::NotMarketParticipant.into());
+  }
+  Ok(())
+}
+
+fn process_propose_market_resolver(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  new_resolver: Pubkey,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let authority_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let config_info = next_account_info(account_info_iter)?;
+  assert_market_owner(prediction_market_info.owner)?;
+  let config = load_program_config(program_id, config_info)?;
+
+  if !authority_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  if !market.resolver.eq(authority_info.key) && !config.admin.eq(authority_info.key) {
+    return Err(HitMyBetError::InvalidResolverAuthority.into());
+  }
+
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+
+  market.pending_resolver = Some(new_resolver);
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  msg!("resolver proposed: {}", new_resolver);
+
+  Ok(())
+}
+
+fn process_accept_market_resolver(accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let new_resolver_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  assert_market_owner(prediction_market_info.owner)?;
+
+  if !new_resolver_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  if market.pending_resolver != Some(*new_resolver_info.key) {
+    return Err(HitMyBetError::InvalidResolverAuthority.into());
+  }
+
+  if market.resolution != MarketResolution::Unresolved {
+    return Err(HitMyBetError::MarketIsResolved.into());
+  }
+
+  market.resolver = *new_resolver_info.key;
+  market.pending_resolver = None;
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  msg!("resolver updated");
+
+  Ok(())
+}
+
+fn process_resolve_market_admin(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  resolution: MarketResolution,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let resolver_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let config_info = next_account_info(account_info_iter)?;
+  let resolver_set_info = next_account_info(account_info_iter)?;
+  assert_market_owner(prediction_market_info.owner)?;
+  let config = load_program_config(program_id, config_info)?;
+
+  if !resolver_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  if !config.admin.eq(resolver_info.key) {
+    return Err(HitMyBetError::InvalidResolver.into());
+  }
+
+  // The admin settles disputes and stands in for resolvers that cannot resolve a closed market,
+  // but never overrides a resolution that is proposed or final.
+  match market.resolution_status {
+    ResolutionStatus::UnderDispute => {}
+    ResolutionStatus::Proposed => return Err(HitMyBetError::MarketIsResolved.into()),
+    ResolutionStatus::Final => {
+      if market.resolution != MarketResolution::Unresolved {
+        return Err(HitMyBetError::MarketIsResolved.into());
+      }
+      let clock = solana_program::clock::Clock::get()?;
+      // Resolvers of a set keep the market until they deadlock or let the timeout run out, and
+      // oracle markets until the window for a price after `resolve_at` has passed.
+      let admin_from = if market.has_resolver_set {
+        if resolver_set_is_deadlocked(
+          program_id,
+          prediction_market_info,
+          resolver_set_info,
+          &market,
+        )? {
+          clock.unix_timestamp
+        } else {
+          market
+            .open_until
+            .checked_add(ResolverSet::RESOLUTION_TIMEOUT)
+            .ok_or(HitMyBetError::MathOverflow)?
+        }
+      } else if let Some(oracle) = market.oracle {
+        oracle
+          .resolve_at
+          .checked_add(oracle::MAX_PRICE_AGE + 1)
+          .ok_or(HitMyBetError::MathOverflow)?
+      } else {
+        market.open_until
+      };
+      if clock.unix_timestamp < admin_from {
+        return Err(HitMyBetError::MarketIsOpen.into());
+      }
+    }
+  }
+
+  assert_resolution_kind(&market, &resolution)?;
+
+  let proposal_upheld = market.resolution == resolution;
+  set_resolution(&mut market, resolution, 0)?;
+  if held_bonds(&market)? > 0 {
+    settle_resolution_bonds(
+      program_id,
+      account_info_iter,
+      prediction_market_info,
+      &mut market,
+      proposal_upheld,
+    )?;
+  }
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  msg!("resolution: {:?}", market.resolution);
+
+  Ok(())
+}
+
+fn process_claim_market(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let claimer_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let user_prediction_info = next_account_info(account_info_iter)?;
+  let liquidity_position_info = next_account_info(account_info_iter)?;
+  let treasury_info = next_account_info(account_info_iter)?;
+  assert_market_owner(prediction_market_info.owner)?;
+  if !claimer_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let (user_prediction_pda, _) = Pubkey::find_program_address(
+    &[
+      UserPrediction::SEED_PREFIX.as_bytes(),
+      prediction_market_info.key.as_ref(),
+      claimer_info.key.as_ref(),
+    ],
+    program_id,
+  );
+  if !user_prediction_info.key.eq(&user_prediction_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  let (liquidity_position_pda, _) =
+    LiquidityPosition::find_address(program_id, prediction_market_info.key, claimer_info.key);
+  if !liquidity_position_info.key.eq(&liquidity_position_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  let (treasury_pda, _) = Treasury::find_address(program_id);
+  if !treasury_info.key.eq(&treasury_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  assert_resolution_final(&market)?;
+  if claim_deadline_passed(&market)? {
+    return Err(HitMyBetError::ClaimDeadlinePassed.into());
+  }
+
+  let liquidity_payout = if liquidity_position_info.data_is_empty() {
+    0
+  } else {
+    let position: LiquidityPosition =
+      borsh::BorshDeserialize::deserialize(&mut liquidity_position_info.data.borrow().as_ref())?;
+    market.open_positions = math::sub(market.open_positions, 1)?;
+    withdraw_liquidity_payout(&mut market, &position)?
+  };
+
+  let mut prediction: UserPrediction = if user_prediction_info.data_is_empty() {
+    UserPrediction::default()
+  } else {
+    borsh::BorshDeserialize::deserialize(&mut user_prediction_info.data.borrow().as_ref())?
+  };
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  // Token markets pay the protocol fee into the treasury vault of their collateral mint.
+  let treasury_collateral_infos = match &collateral_infos {
+    Some(collateral) => {
+      let treasury_vault_info = next_account_info(account_info_iter)?;
+      let (treasury_vault_pda, _) = Treasury::find_vault_address(program_id, collateral.mint.key);
+      if !treasury_vault_info.key.eq(&treasury_vault_pda) {
+        return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+      }
+      Some(CollateralInfos {
+        token_account: treasury_vault_info,
+        mint: collateral.mint,
+        vault: collateral.vault,
+        token_program: collateral.token_program,
+      })
+    }
+    None => None,
+  };
+
+  // Outcome shares are burned in full and claimed as votes on their side, alongside any votes
+  // recorded in the claimer's UserPrediction account.
+  if let Some(shares) = next_share_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )? {
+    let amount = token_account_amount(shares.token_account)?;
+    burn_shares(claimer_info, &shares, amount)?;
+    add_votes(&mut prediction, &market.kind.side_vote(shares.side), amount)?;
+    msg!("burned shares: side={},amount={}", shares.side, amount);
+  }
+
+  let resolution = payout_resolution(&market);
+
+  let winnings: u64;
+  let refund: u64;
+  let votes_to_claim: u64;
+  match resolution {
+    MarketResolution::Unresolved => {
+      return Err(HitMyBetError::MarketIsNotResolved.into());
+    }
+    // Market maker shares pay one vote price per winning share and half of it per share on a tie.
+    _ if market.pricing != MarketPricing::Parimutuel => {
+      refund = 0;
+      if resolution == MarketResolution::Tie {
+        votes_to_claim = math::add(prediction.num_votes_yes, prediction.num_votes_no)?;
+        winnings = math::mul_div(votes_to_claim, market.vote_price, 2)?;
+      } else {
+        votes_to_claim = if resolution == MarketResolution::Yes {
+          prediction.num_votes_yes
+        } else {
+          prediction.num_votes_no
+        };
+        winnings = math::mul(votes_to_claim, market.vote_price)?;
+      }
+    }
+    MarketResolution::Yes => {
+      votes_to_claim = prediction.num_votes_yes;
+      winnings = math::pool_share(market.balance_no, votes_to_claim, market.num_yes)?;
+      refund = math::pool_share(market.balance_yes, votes_to_claim, market.num_yes)?;
+    }
+    MarketResolution::No => {
+      votes_to_claim = prediction.num_votes_no;
+      winnings = math::pool_share(market.balance_yes, votes_to_claim, market.num_no)?;
+      refund = math::pool_share(market.balance_no, votes_to_claim, market.num_no)?;
+    }
+    MarketResolution::Outcome(outcome) => {
+      let outcome = usize::from(outcome);
+      votes_to_claim = prediction.outcome_votes[outcome];
+      let losing_balance = math::sum(
+        market
+          .outcome_balances
+          .iter()
+          .enumerate()
+          .filter(|(index, _)| *index != outcome)
+          .map(|(_, balance)| *balance),
+      )?;
+      winnings = math::pool_share(
+        losing_balance,
+        votes_to_claim,
+        market.outcome_votes[outcome],
+      )?;
+      refund = math::pool_share(
+        market.outcome_balances[outcome],
+        votes_to_claim,
+        market.outcome_votes[outcome],
+      )?;
+    }
+    MarketResolution::Scalar(value) => {
+      let MarketKind::Scalar { min, max } = market.kind else {
+        return Err(HitMyBetError::InvalidMarketKind.into());
+      };
+      votes_to_claim = math::add(prediction.num_votes_yes, prediction.num_votes_no)?;
+      let payout = math::scalar_payout(
+        (market.balance_yes, market.balance_no),
+        (market.num_yes, market.num_no),
+        (prediction.num_votes_yes, prediction.num_votes_no),
+        (min, max),
+        value,
+      )?;
+      // The staked share of both pools is returned first; only the payout above it is won.
+      let stake = math::add(
+        math::pool_share(market.balance_yes, prediction.num_votes_yes, market.num_yes)?,
+        math::pool_share(market.balance_no, prediction.num_votes_no, market.num_no)?,
+      )?;
+      refund = payout.min(stake);
+      winnings = math::sub(payout, refund)?;
+    }
+    MarketResolution::Tie => {
+      winnings = 0;
+      votes_to_claim = math::sum(
+        [prediction.num_votes_yes, prediction.num_votes_no]
+          .into_iter()
+          .chain(prediction.outcome_votes),
+      )?;
+      let mut refunds = vec![
+        math::pool_share(market.balance_yes, prediction.num_votes_yes, market.num_yes)?,
+        math::pool_share(market.balance_no, prediction.num_votes_no, market.num_no)?,
+      ];
+      for outcome in 0..MAX_OUTCOMES {
+        refunds.push(math::pool_share(
+          market.outcome_balances[outcome],
+          prediction.outcome_votes[outcome],
+          market.outcome_votes[outcome],
+        )?);
+      }
+      refund = math::sum(refunds)?;
+    }
+  };
+
+  // Every pro-rata share rounds down; the claim that settles the last payable vote also takes the
+  // remainder left by the claims before it.
+  let (payable_votes, payable_pool) = payable_pool(&market, resolution)?;
+  market.claimed_votes = math::add(market.claimed_votes, votes_to_claim)?;
+  let mut dust = 0;
+  if market.pricing == MarketPricing::Parimutuel
+    && votes_to_claim > 0
+    && market.claimed_votes == payable_votes
+  {
+    dust = math::sub(
+      math::sub(payable_pool, market.total_claimed)?,
+      math::add(winnings, refund)?,
+    )?;
+  }
+  let gross = math::sum([winnings, refund, dust, liquidity_payout])?;
+  market.total_claimed = math::add(market.total_claimed, gross)?;
+
+  // Fees are only taken from winnings; refunded stakes, dust and liquidity are paid in full. The
+  // creator's cut stays in the market until WithdrawCreatorFees.
+  let protocol_fee = math::bps(winnings, market.protocol_fee_bps.into())?;
+  let creator_fee = math::bps(winnings, market.creator_fee_bps.into())?;
+  market.creator_fees = math::add(market.creator_fees, creator_fee)?;
+  let fee = math::add(protocol_fee, creator_fee)?;
+  let net = math::sub(gross, fee)?;
+
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+  close_program_account(claimer_info, liquidity_position_info)?;
+
+  if votes_to_claim == 0 && liquidity_payout == 0 {
+    msg!("lost prediction: {:?}", prediction);
+
+    close_program_account(claimer_info, user_prediction_info)?;
+
+    return Ok(());
   } else {
-    let lamports_to_claim = (win_per_vote * votes_to_claim) + (votes_to_claim * VOTE_PRICE);
     msg!(
-      "win prediction: {:?},win={},refund={},total={},per_vote={},votes={}",
+      "win prediction: {:?},win={},refund={},dust={},liquidity={},gross={},fee={},net={},votes={}",
       prediction,
-      win_per_vote * votes_to_claim,
-      votes_to_claim * VOTE_PRICE,
-      lamports_to_claim,
-      win_per_vote,
+      winnings,
+      refund,
+      dust,
+      liquidity_payout,
+      gross,
+      fee,
+      net,
       votes_to_claim
     );
-    let dest_starting_lamports = claimer_info.lamports();
-    **claimer_info.lamports.borrow_mut() = dest_starting_lamports
-      .checked_add(user_prediction_info.lamports())
-      .unwrap()
-      .checked_add(lamports_to_claim)
-      .unwrap();
-    **user_prediction_info.lamports.borrow_mut() = 0;
-    **prediction_market_info.lamports.borrow_mut() -= lamports_to_claim;
-
-    user_prediction_info.assign(&solana_program::system_program::ID);
-    user_prediction_info.realloc(0, false)?;
+    pay_out(
+      &market,
+      prediction_market_info,
+      claimer_info,
+      collateral_infos.as_ref(),
+      net,
+    )?;
+    if protocol_fee > 0 {
+      pay_out(
+        &market,
+        prediction_market_info,
+        treasury_info,
+        treasury_collateral_infos.as_ref(),
+        protocol_fee,
+      )?;
+      if treasury_collateral_infos.is_none() {
+        add_treasury_fees(treasury_info, protocol_fee)?;
+      }
+    }
+
+    close_program_account(claimer_info, user_prediction_info)?;
+
+    // invoke_signed(
+    //   &system_instruction::transfer(&prediction_market_info.key, claimer_info.key, lamports_to_claim),
+    //   &[prediction_market_info.clone(), claimer_info.clone()],
+    //   &[],
+    // )?;
+  }
+
+  Ok(())
+}
+
+/// Closes a resolved market once every winning vote and liquidity position has been claimed, or
+/// the unclaimed remainder has been swept, and the order book is empty. Whatever is left in the
+/// market, its rent and rounding remainders included, goes to the creator; token markets also
+/// empty and close the vault.
+fn process_close_market(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let creator_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let order_book_info = next_account_info(account_info_iter)?;
+  let resolver_set_info = next_account_info(account_info_iter)?;
+  let share_token_program = next_account_info(account_info_iter)?;
+  if !creator_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_market_owner(prediction_market_info.owner)?;
+  if !spl_token::check_id(share_token_program.key) {
+    return Err(HitMyBetError::IncorrectProgramId.into());
+  }
+
+  let market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+  let share_mint_infos = (0..market.kind.num_sides())
+    .map(|_| next_account_info(account_info_iter))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  if !market.creator.eq(creator_info.key) {
+    return Err(HitMyBetError::InvalidCreator.into());
+  }
+  assert_resolution_final(&market)?;
+  if held_bonds(&market)? > 0 {
+    return Err(HitMyBetError::BondsOutstanding.into());
+  }
+  let (payable_votes, _) = payable_pool(&market, payout_resolution(&market))?;
+  if !market.swept && (market.claimed_votes < payable_votes || market.open_positions > 0) {
+    msg!(
+      "claimed={},payable={},positions={}",
+      market.claimed_votes,
+      payable_votes,
+      market.open_positions
+    );
+    return Err(HitMyBetError::ClaimsOutstanding.into());
+  }
+
+  let (order_book_pda, _) = OrderBook::find_address(program_id, prediction_market_info.key);
+  if !order_book_info.key.eq(&order_book_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  if !order_book_info.data_is_empty() {
+    assert_market_owner(order_book_info.owner)?;
+    let order_book: OrderBook =
+      borsh::BorshDeserialize::deserialize(&mut order_book_info.data.borrow().as_ref())?;
+    if !order_book.orders.is_empty() {
+      return Err(HitMyBetError::ClaimsOutstanding.into());
+    }
+  }
+  let (resolver_set_pda, _) = ResolverSet::find_address(program_id, prediction_market_info.key);
+  if !resolver_set_info.key.eq(&resolver_set_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  if !resolver_set_info.data_is_empty() {
+    assert_market_owner(resolver_set_info.owner)?;
+  }
+
+  // Legacy SPL mints cannot be closed, so the share mints are frozen at their supply instead.
+  for (side, share_mint_info) in (0..).zip(share_mint_infos) {
+    let (share_mint_pda, _) =
+      PredictionMarket::find_share_mint_address(program_id, prediction_market_info.key, side);
+    if !share_mint_info.key.eq(&share_mint_pda) {
+      return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+    }
+    revoke_share_mint_authority(
+      &market,
+      prediction_market_info,
+      share_mint_info,
+      share_token_program,
+    )?;
+  }
+  close_program_account(creator_info, order_book_info)?;
+  close_program_account(creator_info, resolver_set_info)?;
+
+  if let Some(collateral) = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )? {
+    let residual = token_account_amount(collateral.vault)?;
+    if residual > 0 {
+      transfer_from_vault(&market, prediction_market_info, &collateral, residual)?;
+    }
+    close_market_vault(&market, prediction_market_info, &collateral, creator_info)?;
+    msg!("vault residual={}", residual);
+  }
+
+  let residual_lamports = prediction_market_info.lamports();
+  close_program_account(creator_info, prediction_market_info)?;
+
+  msg!(
+    "market closed, claimed={},lamports={}",
+    market.total_claimed,
+    residual_lamports
+  );
+
+  Ok(())
+}
+
+/// Moves what is left in a market after its claim deadline, except its rent, the escrow of open
+/// bids, accrued creator fees and held resolution bonds, to the treasury. Anyone can call it; the
+/// caller pays for creating the treasury account or treasury vault the first time.
+fn process_sweep_unclaimed(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let cranker_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  let order_book_info = next_account_info(account_info_iter)?;
+  let treasury_info = next_account_info(account_info_iter)?;
+  let system_program = next_account_info(account_info_iter)?;
+  if !cranker_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_market_owner(prediction_market_info.owner)?;
+  assert_system_program(system_program.key)?;
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  assert_resolution_final(&market)?;
+  if !claim_deadline_passed(&market)? {
+    return Err(HitMyBetError::ClaimDeadlineNotReached.into());
+  }
+
+  let (order_book_pda, _) = OrderBook::find_address(program_id, prediction_market_info.key);
+  if !order_book_info.key.eq(&order_book_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  let bid_escrow = if order_book_info.data_is_empty() {
+    0
+  } else {
+    let order_book: OrderBook =
+      borsh::BorshDeserialize::deserialize(&mut order_book_info.data.borrow().as_ref())?;
+    math::sum(
+      order_book
+        .orders
+        .iter()
+        .filter(|order| order.side == OrderSide::Bid)
+        .map(|order| math::mul(order.price, order.quantity))
+        .collect::<Result<Vec<_>, _>>()?,
+    )?
+  };
+
+  load_or_create_treasury(program_id, cranker_info, treasury_info, system_program)?;
+  // Open bids and accrued creator fees are not unclaimed winnings.
+  let reserved = math::add(bid_escrow, market.creator_fees)?;
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+  let swept = match collateral_infos {
+    Some(collateral) => {
+      create_treasury_vault_if_needed(
+        program_id,
+        cranker_info,
+        treasury_info,
+        collateral.mint,
+        collateral.token_account,
+        collateral.token_program,
+        system_program,
+      )?;
+      let swept = math::sub(token_account_amount(collateral.vault)?, reserved)?;
+      if swept > 0 {
+        transfer_from_vault(&market, prediction_market_info, &collateral, swept)?;
+      }
+      swept
+    }
+    None => {
+      // Resolution bonds are held in lamports alongside the pools.
+      let kept = math::sum([
+        Rent::get()?.minimum_balance(PredictionMarket::LEN),
+        reserved,
+        held_bonds(&market)?,
+      ])?;
+      let swept = math::sub(prediction_market_info.lamports(), kept)?;
+      let treasury_starting_lamports = treasury_info.lamports();
+      **prediction_market_info.lamports.borrow_mut() = kept;
+      **treasury_info.lamports.borrow_mut() = math::add(treasury_starting_lamports, swept)?;
+      add_treasury_fees(treasury_info, swept)?;
+      swept
+    }
+  };
+
+  market.swept = true;
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  msg!("swept unclaimed={}", swept);
+
+  Ok(())
+}
+
+/// Withdraws protocol revenue from the treasury. Lamport withdrawals leave the treasury its rent,
+/// and token withdrawals go to a token account of the recipient.
+fn process_withdraw_treasury(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  amount: u64,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let admin_info = next_account_info(account_info_iter)?;
+  let config_info = next_account_info(account_info_iter)?;
+  let treasury_info = next_account_info(account_info_iter)?;
+  let recipient_info = next_account_info(account_info_iter)?;
+  if !admin_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let config = load_program_config(program_id, config_info)?;
+  if !config.admin.eq(admin_info.key) {
+    return Err(HitMyBetError::InvalidAdmin.into());
+  }
+
+  let (treasury_pda, _) = Treasury::find_address(program_id);
+  if !treasury_info.key.eq(&treasury_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  assert_market_owner(treasury_info.owner)?;
+  let treasury: Treasury =
+    borsh::BorshDeserialize::deserialize(&mut treasury_info.data.borrow().as_ref())?;
+
+  match account_info_iter.next() {
+    Some(token_account) => {
+      let mint_info = next_account_info(account_info_iter)?;
+      let vault = next_account_info(account_info_iter)?;
+      let token_program = next_account_info(account_info_iter)?;
+      assert_token_program(token_program.key)?;
+      if !mint_info.owner.eq(token_program.key) {
+        return Err(HitMyBetError::InvalidCollateral.into());
+      }
+      if !token_account_owner(token_account)?.eq(recipient_info.key) {
+        return Err(HitMyBetError::InvalidRecipient.into());
+      }
+      let (vault_pda, _) = Treasury::find_vault_address(program_id, mint_info.key);
+      if !vault.key.eq(&vault_pda) {
+        return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+      }
+      if amount > token_account_amount(vault)? {
+        return Err(HitMyBetError::InsufficientTreasuryFunds.into());
+      }
+      invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+          token_program.key,
+          vault.key,
+          mint_info.key,
+          token_account.key,
+          treasury_info.key,
+          &[],
+          amount,
+          mint_decimals(mint_info)?,
+        )?,
+        &[
+          vault.clone(),
+          mint_info.clone(),
+          token_account.clone(),
+          treasury_info.clone(),
+          token_program.clone(),
+        ],
+        &[&[Treasury::SEED_PREFIX.as_bytes(), &[treasury.bump_seed]]],
+      )?;
+      msg!(
+        "treasury withdrawal: mint={},amount={}",
+        mint_info.key,
+        amount
+      );
+    }
+    None => {
+      let rent = Rent::get()?.minimum_balance(Treasury::LEN);
+      let available = math::sub(treasury_info.lamports(), rent)?;
+      if amount > available {
+        return Err(HitMyBetError::InsufficientTreasuryFunds.into());
+      }
+      let recipient_starting_lamports = recipient_info.lamports();
+      **treasury_info.lamports.borrow_mut() = math::sub(treasury_info.lamports(), amount)?;
+      **recipient_info.lamports.borrow_mut() = math::add(recipient_starting_lamports, amount)?;
+      msg!("treasury withdrawal: lamports={}", amount);
+    }
+  }
+
+  Ok(())
+}
+
+/// Loads the protocol treasury, creating it at the expense of `payer_info` on first use.
+fn load_or_create_treasury<'a>(
+  program_id: &Pubkey,
+  payer_info: &AccountInfo<'a>,
+  treasury_info: &AccountInfo<'a>,
+  system_program: &AccountInfo<'a>,
+) -> Result<Treasury, ProgramError> {
+  let (treasury_pda, treasury_bump) = Treasury::find_address(program_id);
+  if !treasury_info.key.eq(&treasury_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  if !treasury_info.data_is_empty() {
+    return Ok(borsh::BorshDeserialize::deserialize(
+      &mut treasury_info.data.borrow().as_ref(),
+    )?);
+  }
+
+  let rent = Rent::get()?;
+  invoke_signed(
+    &system_instruction::create_account(
+      payer_info.key,
+      treasury_info.key,
+      rent.minimum_balance(Treasury::LEN),
+      u64::try_from(Treasury::LEN).expect("data size"),
+      program_id,
+    ),
+    &[
+      payer_info.clone(),
+      treasury_info.clone(),
+      system_program.clone(),
+    ],
+    &[&[Treasury::SEED_PREFIX.as_bytes(), &[treasury_bump]]],
+  )?;
+  let treasury = Treasury::with_seed(treasury_bump);
+  borsh::BorshSerialize::serialize(&treasury, &mut &mut treasury_info.data.borrow_mut()[..])?;
+  Ok(treasury)
+}
+
+/// Adds `amount` lamports just paid into the treasury to its total fees.
+fn add_treasury_fees(treasury_info: &AccountInfo, amount: u64) -> ProgramResult {
+  let mut treasury: Treasury =
+    borsh::BorshDeserialize::deserialize(&mut treasury_info.data.borrow().as_ref())?;
+  treasury.total_fees = math::add(treasury.total_fees, amount)?;
+  borsh::BorshSerialize::serialize(&treasury, &mut &mut treasury_info.data.borrow_mut()[..])?;
+  Ok(())
+}
+
+/// Creates the treasury vault of `mint_info` on first use, at the expense of `payer_info`.
+fn create_treasury_vault_if_needed<'a>(
+  program_id: &Pubkey,
+  payer_info: &AccountInfo<'a>,
+  treasury_info: &AccountInfo<'a>,
+  mint_info: &AccountInfo<'a>,
+  treasury_vault_info: &AccountInfo<'a>,
+  token_program: &AccountInfo<'a>,
+  system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+  let (treasury_vault_pda, treasury_vault_bump) =
+    Treasury::find_vault_address(program_id, mint_info.key);
+  if !treasury_vault_info.key.eq(&treasury_vault_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  if !treasury_vault_info.data_is_empty() {
+    return Ok(());
+  }
+  create_token_vault(
+    payer_info,
+    treasury_info.key,
+    mint_info,
+    treasury_vault_info,
+    token_program,
+    system_program,
+    &[
+      Treasury::VAULT_SEED_PREFIX.as_bytes(),
+      mint_info.key.as_ref(),
+      &[treasury_vault_bump],
+    ],
+  )
+}
+
+/// Pays the creator fees accrued by a market to its creator.
+fn process_withdraw_creator_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let creator_info = next_account_info(account_info_iter)?;
+  let prediction_market_info = next_account_info(account_info_iter)?;
+  if !creator_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_market_owner(prediction_market_info.owner)?;
+
+  let mut market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut prediction_market_info.data.borrow().as_ref())?;
+
+  if !market.creator.eq(creator_info.key) {
+    return Err(HitMyBetError::InvalidCreator.into());
+  }
+
+  let collateral_infos = next_collateral_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    &market,
+  )?;
+
+  let amount = market.creator_fees;
+  market.creator_fees = 0;
+  borsh::BorshSerialize::serialize(
+    &market,
+    &mut &mut prediction_market_info.data.borrow_mut()[..],
+  )?;
+
+  if amount > 0 {
+    pay_out(
+      &market,
+      prediction_market_info,
+      creator_info,
+      collateral_infos.as_ref(),
+      amount,
+    )?;
+  }
+
+  msg!("creator fees withdrawn={}", amount);
+
+  Ok(())
+}
+
+fn claim_deadline_passed(market: &PredictionMarket) -> Result<bool, ProgramError> {
+  if market.claim_deadline == 0 {
+    return Ok(false);
+  }
+  let clock = solana_program::clock::Clock::get()?;
+  Ok(clock.unix_timestamp >= market.claim_deadline)
+}
+
+/// Moves every lamport of a program account, rent included, to `recipient_info` and closes it.
+/// Claims made only with outcome shares have no UserPrediction account to close.
+fn close_program_account(
+  recipient_info: &AccountInfo,
+  account_info: &AccountInfo,
+) -> ProgramResult {
+  if account_info.data_is_empty() {
+    return Ok(());
+  }
+
+  let dest_starting_lamports = recipient_info.lamports();
+  **recipient_info.lamports.borrow_mut() =
+    math::add(dest_starting_lamports, account_info.lamports())?;
+  **account_info.lamports.borrow_mut() = 0;
+
+  account_info.assign(&solana_program::system_program::ID);
+  account_info.realloc(0, false)?;
+
+  Ok(())
+}
+
+/// Takes the position's share of the pool reserves out of a resolved constant-product market and
+/// returns what the position is owed for the resolution.
+fn withdraw_liquidity_payout(
+  market: &mut PredictionMarket,
+  position: &LiquidityPosition,
+) -> Result<u64, HitMyBetError> {
+  let reserve_yes = math::pool_share(market.reserve_yes, position.lp_shares, market.lp_supply)?;
+  let reserve_no = math::pool_share(market.reserve_no, position.lp_shares, market.lp_supply)?;
+  market.reserve_yes = math::sub(market.reserve_yes, reserve_yes)?;
+  market.reserve_no = math::sub(market.reserve_no, reserve_no)?;
+  market.lp_supply = math::sub(market.lp_supply, position.lp_shares)?;
+
+  let exposure_yes = math::add(reserve_yes, position.excess_yes)?;
+  let exposure_no = math::add(reserve_no, position.excess_no)?;
+  match market.resolution {
+    MarketResolution::Yes => Ok(exposure_yes),
+    MarketResolution::No => Ok(exposure_no),
+    MarketResolution::Tie => Ok(math::add(exposure_yes, exposure_no)? / 2),
+    _ => Ok(0),
+  }
+}
+
+fn add_votes(
+  prediction: &mut UserPrediction,
+  vote: &UserVote,
+  num_votes: u64,
+) -> Result<(), HitMyBetError> {
+  let held = match vote {
+    UserVote::Unspecified => return Ok(()),
+    UserVote::Yes => &mut prediction.num_votes_yes,
+    UserVote::No => &mut prediction.num_votes_no,
+    UserVote::Outcome(outcome) => &mut prediction.outcome_votes[usize::from(*outcome)],
+  };
+  *held = math::add(*held, num_votes)?;
+  Ok(())
+}
+
+fn process_initialize_config(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  create_market_fee: u64,
+  vote_price: u64,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let payer_info = next_account_info(account_info_iter)?;
+  let config_info = next_account_info(account_info_iter)?;
+  let admin_info = next_account_info(account_info_iter)?;
+  let program_data_info = next_account_info(account_info_iter)?;
+  let system_program = next_account_info(account_info_iter)?;
+  if !payer_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  if !admin_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+  assert_system_program(system_program.key)?;
+  assert_upgrade_authority(program_id, program_data_info, admin_info.key)?;
+
+  let (config_pda, config_bump) =
+    Pubkey::find_program_address(&[ProgramConfig::SEED_PREFIX.as_bytes()], program_id);
+  if !config_info.key.eq(&config_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  if !config_info.data_is_empty() {
+    return Err(HitMyBetError::AlreadyInitialized.into());
+  }
+
+  let rent = Rent::get()?;
+  let rent_lamports = rent.minimum_balance(ProgramConfig::LEN);
+
+  invoke_signed(
+    &system_instruction::create_account(
+      payer_info.key,
+      config_info.key,
+      rent_lamports,
+      u64::try_from(ProgramConfig::LEN).expect("data size"),
+      program_id,
+    ),
+    &[
+      payer_info.clone(),
+      config_info.clone(),
+      system_program.clone(),
+    ],
+    &[&[ProgramConfig::SEED_PREFIX.as_bytes(), &[config_bump]]],
+  )?;
+
+  borsh::BorshSerialize::serialize(
+    &ProgramConfig::with_params(InitProgramConfigParams {
+      bump_seed: config_bump,
+      admin: *admin_info.key,
+      create_market_fee,
+      vote_price,
+    }),
+    &mut &mut config_info.data.borrow_mut()[..],
+  )?;
 
-    // invoke_signed(
-    //   &system_instruction::transfer(&prediction_market_info.key, claimer_info.key, lamports_to_claim),
-    //   &[prediction_market_info.clone(), claimer_info.clone()],
-    //   &[],
-    // )?;
+  msg!("config initialized, admin: {}", admin_info.key);
+
+  Ok(())
+}
+
+/// Only the program's upgrade authority can bootstrap the config, as its first admin. It reads the
+/// `UpgradeableLoaderState::ProgramData` header: a u32 variant tag of 3, the deployment slot and
+/// the optional upgrade authority.
+fn assert_upgrade_authority(
+  program_id: &Pubkey,
+  program_data_info: &AccountInfo,
+  authority: &Pubkey,
+) -> ProgramResult {
+  if !program_data_info
+    .key
+    .eq(&bpf_loader_upgradeable::get_program_data_address(
+      program_id,
+    ))
+    || !bpf_loader_upgradeable::check_id(program_data_info.owner)
+  {
+    return Err(HitMyBetError::InvalidUpgradeAuthority.into());
+  }
+  let data = program_data_info.data.borrow();
+  let is_authority = data
+    .get(..UpgradeableLoaderState::size_of_programdata_metadata())
+    .is_some_and(|metadata| {
+      metadata[..4] == 3u32.to_le_bytes()
+        && metadata[12] == 1
+        && metadata[13..] == authority.to_bytes()
+    });
+  if !is_authority {
+    return Err(HitMyBetError::InvalidUpgradeAuthority.into());
+  }
+  Ok(())
+}
+
+fn process_update_config(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  create_market_fee: u64,
+  vote_price: u64,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let admin_info = next_account_info(account_info_iter)?;
+  let config_info = next_account_info(account_info_iter)?;
+  if !admin_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut config = load_program_config(program_id, config_info)?;
+
+  if !config.admin.eq(admin_info.key) {
+    return Err(HitMyBetError::InvalidAdmin.into());
+  }
+
+  config.create_market_fee = create_market_fee;
+  config.vote_price = vote_price;
+
+  borsh::BorshSerialize::serialize(&config, &mut &mut config_info.data.borrow_mut()[..])?;
+
+  msg!(
+    "config updated, create_market_fee={},vote_price={}",
+    create_market_fee,
+    vote_price
+  );
+
+  Ok(())
+}
+
+fn process_propose_admin(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  new_admin: Pubkey,
+) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let admin_info = next_account_info(account_info_iter)?;
+  let config_info = next_account_info(account_info_iter)?;
+  if !admin_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut config = load_program_config(program_id, config_info)?;
+
+  if !config.admin.eq(admin_info.key) {
+    return Err(HitMyBetError::InvalidAdmin.into());
+  }
+
+  config.pending_admin = Some(new_admin);
+
+  borsh::BorshSerialize::serialize(&config, &mut &mut config_info.data.borrow_mut()[..])?;
+
+  msg!("admin proposed: {}", new_admin);
+
+  Ok(())
+}
+
+fn process_accept_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let new_admin_info = next_account_info(account_info_iter)?;
+  let config_info = next_account_info(account_info_iter)?;
+  if !new_admin_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut config = load_program_config(program_id, config_info)?;
+
+  if config.pending_admin != Some(*new_admin_info.key) {
+    return Err(HitMyBetError::InvalidAdmin.into());
+  }
+
+  config.admin = *new_admin_info.key;
+  config.pending_admin = None;
+
+  borsh::BorshSerialize::serialize(&config, &mut &mut config_info.data.borrow_mut()[..])?;
+
+  msg!("admin updated: {}", new_admin_info.key);
+
+  Ok(())
+}
+
+fn process_cancel_admin_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  let account_info_iter = &mut accounts.iter();
+  let admin_info = next_account_info(account_info_iter)?;
+  let config_info = next_account_info(account_info_iter)?;
+  if !admin_info.is_signer {
+    return Err(HitMyBetError::InvalidSigner.into());
+  }
+
+  let mut config = load_program_config(program_id, config_info)?;
+
+  if !config.admin.eq(admin_info.key) {
+    return Err(HitMyBetError::InvalidAdmin.into());
   }
 
+  config.pending_admin = None;
+
+  borsh::BorshSerialize::serialize(&config, &mut &mut config_info.data.borrow_mut()[..])?;
+
+  msg!("admin proposal cancelled");
+
   Ok(())
 }
 
+fn load_program_config(
+  program_id: &Pubkey,
+  config_info: &AccountInfo,
+) -> Result<ProgramConfig, ProgramError> {
+  if !crate::check_id(config_info.owner) {
+    return Err(HitMyBetError::InvalidAccountOwner.into());
+  }
+  let (config_pda, _) =
+    Pubkey::find_program_address(&[ProgramConfig::SEED_PREFIX.as_bytes()], program_id);
+  if !config_info.key.eq(&config_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  let config: ProgramConfig =
+    borsh::BorshDeserialize::deserialize(&mut config_info.data.borrow().as_ref())?;
+  Ok(config)
+}
+
+fn assert_resolution_kind(
+  market: &PredictionMarket,
+  resolution: &MarketResolution,
+) -> ProgramResult {
+  match (resolution, market.kind) {
+    (MarketResolution::Tie, _) => Ok(()),
+    (MarketResolution::Yes | MarketResolution::No, MarketKind::Binary) => Ok(()),
+    (MarketResolution::Outcome(outcome), MarketKind::Categorical { num_outcomes }) => {
+      if *outcome >= num_outcomes {
+        Err(HitMyBetError::InvalidOutcome.into())
+      } else {
+        Ok(())
+      }
+    }
+    (MarketResolution::Scalar(value), MarketKind::Scalar { min, max }) => {
+      if *value < min || *value > max {
+        Err(HitMyBetError::InvalidScalarRange.into())
+      } else {
+        Ok(())
+      }
+    }
+    _ => Err(HitMyBetError::InvalidMarketKind.into()),
+  }
+}
+
+/// Mint extensions that let a third party move, lock or hide tokens held by the market vault.
+const UNSUPPORTED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+  ExtensionType::PermanentDelegate,
+  ExtensionType::NonTransferable,
+  ExtensionType::TransferHook,
+  ExtensionType::ConfidentialTransferMint,
+];
+
+struct ShareInfos<'a, 'b> {
+  side: u8,
+  share_mint: &'a AccountInfo<'b>,
+  token_account: &'a AccountInfo<'b>,
+  token_program: &'a AccountInfo<'b>,
+}
+
+/// Reads the optional trailing `[share_mint, token_account, token_program]` accounts and resolves
+/// which side of the market the share mint belongs to.
+fn next_share_infos<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+  program_id: &Pubkey,
+  account_info_iter: &mut I,
+  prediction_market_info: &AccountInfo<'b>,
+  market: &PredictionMarket,
+) -> Result<Option<ShareInfos<'a, 'b>>, ProgramError> {
+  let Some(share_mint) = account_info_iter.next() else {
+    return Ok(None);
+  };
+  let token_account = next_account_info(account_info_iter)?;
+  let token_program = next_account_info(account_info_iter)?;
+  if !spl_token::check_id(token_program.key) {
+    return Err(HitMyBetError::IncorrectProgramId.into());
+  }
+  let side = (0..market.kind.num_sides())
+    .find(|side| {
+      let (share_mint_pda, _) =
+        PredictionMarket::find_share_mint_address(program_id, prediction_market_info.key, *side);
+      share_mint.key.eq(&share_mint_pda)
+    })
+    .ok_or(HitMyBetError::InvalidShareMint)?;
+  Ok(Some(ShareInfos {
+    side,
+    share_mint,
+    token_account,
+    token_program,
+  }))
+}
+
+/// Reads the required trailing share accounts of the YES side, which order book orders trade.
+fn next_yes_share_infos<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+  program_id: &Pubkey,
+  account_info_iter: &mut I,
+  prediction_market_info: &AccountInfo<'b>,
+  market: &PredictionMarket,
+) -> Result<ShareInfos<'a, 'b>, ProgramError> {
+  let shares = next_share_infos(
+    program_id,
+    account_info_iter,
+    prediction_market_info,
+    market,
+  )?
+  .ok_or(ProgramError::NotEnoughAccountKeys)?;
+  if shares.side != 0 {
+    return Err(HitMyBetError::InvalidShareMint.into());
+  }
+  Ok(shares)
+}
+
+/// Reads the `[yes_mint, yes_token_account, no_mint, no_token_account, token_program]` accounts of
+/// a complete set.
+fn next_complete_set_infos<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+  program_id: &Pubkey,
+  account_info_iter: &mut I,
+  prediction_market_info: &AccountInfo<'b>,
+) -> Result<[ShareInfos<'a, 'b>; 2], ProgramError> {
+  let yes_mint = next_account_info(account_info_iter)?;
+  let yes_token_account = next_account_info(account_info_iter)?;
+  let no_mint = next_account_info(account_info_iter)?;
+  let no_token_account = next_account_info(account_info_iter)?;
+  let token_program = next_account_info(account_info_iter)?;
+  if !spl_token::check_id(token_program.key) {
+    return Err(HitMyBetError::IncorrectProgramId.into());
+  }
+  let shares = [(yes_mint, yes_token_account), (no_mint, no_token_account)];
+  for (side, (share_mint, _)) in (0..).zip(shares) {
+    let (share_mint_pda, _) =
+      PredictionMarket::find_share_mint_address(program_id, prediction_market_info.key, side);
+    if !share_mint.key.eq(&share_mint_pda) {
+      return Err(HitMyBetError::InvalidShareMint.into());
+    }
+  }
+  Ok([0, 1].map(|side| ShareInfos {
+    side,
+    share_mint: shares[usize::from(side)].0,
+    token_account: shares[usize::from(side)].1,
+    token_program,
+  }))
+}
+
+/// Creates the outcome share mint of `side` with the market as mint authority. Shares have no
+/// decimals: one share is one vote.
+fn create_share_mint<'a>(
+  program_id: &Pubkey,
+  payer_info: &AccountInfo<'a>,
+  prediction_market_info: &AccountInfo<'a>,
+  share_mint_info: &AccountInfo<'a>,
+  token_program: &AccountInfo<'a>,
+  system_program: &AccountInfo<'a>,
+  side: u8,
+) -> ProgramResult {
+  let (share_mint_pda, share_mint_bump) =
+    PredictionMarket::find_share_mint_address(program_id, prediction_market_info.key, side);
+  if !share_mint_info.key.eq(&share_mint_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  let rent = Rent::get()?;
+  let mint_len = spl_token::state::Mint::LEN;
+
+  invoke_signed(
+    &system_instruction::create_account(
+      payer_info.key,
+      share_mint_info.key,
+      rent.minimum_balance(mint_len),
+      u64::try_from(mint_len).expect("data size"),
+      token_program.key,
+    ),
+    &[
+      payer_info.clone(),
+      share_mint_info.clone(),
+      system_program.clone(),
+    ],
+    &[&[
+      PredictionMarket::SHARE_MINT_SEED_PREFIX.as_bytes(),
+      prediction_market_info.key.as_ref(),
+      &[side],
+      &[share_mint_bump],
+    ]],
+  )?;
+
+  invoke(
+    &spl_token::instruction::initialize_mint2(
+      token_program.key,
+      share_mint_info.key,
+      prediction_market_info.key,
+      None,
+      0,
+    )?,
+    &[share_mint_info.clone(), token_program.clone()],
+  )
+}
+
+struct CollateralInfos<'a, 'b> {
+  token_account: &'a AccountInfo<'b>,
+  mint: &'a AccountInfo<'b>,
+  vault: &'a AccountInfo<'b>,
+  token_program: &'a AccountInfo<'b>,
+}
+
+/// Reads the trailing `[token_account, mint, vault, token_program]` accounts of a token market
+/// and checks them against the market. Native SOL markets take no extra accounts.
+fn next_collateral_infos<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+  program_id: &Pubkey,
+  account_info_iter: &mut I,
+  prediction_market_info: &AccountInfo<'b>,
+  market: &PredictionMarket,
+) -> Result<Option<CollateralInfos<'a, 'b>>, ProgramError> {
+  let Some(collateral_mint) = market.collateral_mint else {
+    return Ok(None);
+  };
+  let token_account = next_account_info(account_info_iter)?;
+  let mint_info = next_account_info(account_info_iter)?;
+  let vault = next_account_info(account_info_iter)?;
+  let token_program = next_account_info(account_info_iter)?;
+  assert_token_program(token_program.key)?;
+  if !collateral_mint.eq(mint_info.key) || !mint_info.owner.eq(token_program.key) {
+    return Err(HitMyBetError::InvalidCollateral.into());
+  }
+  let (vault_pda, _) = Pubkey::find_program_address(
+    &[
+      PredictionMarket::VAULT_SEED_PREFIX.as_bytes(),
+      prediction_market_info.key.as_ref(),
+    ],
+    program_id,
+  );
+  if !vault.key.eq(&vault_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+  Ok(Some(CollateralInfos {
+    token_account,
+    mint: mint_info,
+    vault,
+    token_program,
+  }))
+}
+
+fn create_market_vault<'a>(
+  program_id: &Pubkey,
+  payer_info: &AccountInfo<'a>,
+  prediction_market_info: &AccountInfo<'a>,
+  mint_info: &AccountInfo<'a>,
+  vault_info: &AccountInfo<'a>,
+  token_program: &AccountInfo<'a>,
+  system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+  assert_token_program(token_program.key)?;
+  if !mint_info.owner.eq(token_program.key) {
+    return Err(HitMyBetError::InvalidCollateral.into());
+  }
+
+  let (vault_pda, vault_bump) = Pubkey::find_program_address(
+    &[
+      PredictionMarket::VAULT_SEED_PREFIX.as_bytes(),
+      prediction_market_info.key.as_ref(),
+    ],
+    program_id,
+  );
+  if !vault_info.key.eq(&vault_pda) {
+    return Err(HitMyBetError::InvalidProgramDerivedAddress.into());
+  }
+
+  create_token_vault(
+    payer_info,
+    prediction_market_info.key,
+    mint_info,
+    vault_info,
+    token_program,
+    system_program,
+    &[
+      PredictionMarket::VAULT_SEED_PREFIX.as_bytes(),
+      prediction_market_info.key.as_ref(),
+      &[vault_bump],
+    ],
+  )
+}
+
+/// Creates the token account PDA `vault_info` of `mint_info` owned by `authority`, rejecting mints
+/// with extensions the program does not support.
+fn create_token_vault<'a>(
+  payer_info: &AccountInfo<'a>,
+  authority: &Pubkey,
+  mint_info: &AccountInfo<'a>,
+  vault_info: &AccountInfo<'a>,
+  token_program: &AccountInfo<'a>,
+  system_program: &AccountInfo<'a>,
+  vault_seeds: &[&[u8]],
+) -> ProgramResult {
+  let vault_len = {
+    let mint_data = mint_info.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let mint_extensions = mint.get_extension_types()?;
+    if mint_extensions
+      .iter()
+      .any(|extension| UNSUPPORTED_MINT_EXTENSIONS.contains(extension))
+    {
+      return Err(HitMyBetError::UnsupportedMint.into());
+    }
+    ExtensionType::try_calculate_account_len::<Account>(
+      &ExtensionType::get_required_init_account_extensions(&mint_extensions),
+    )?
+  };
+
+  let rent = Rent::get()?;
+
+  invoke_signed(
+    &system_instruction::create_account(
+      payer_info.key,
+      vault_info.key,
+      rent.minimum_balance(vault_len),
+      u64::try_from(vault_len).expect("data size"),
+      token_program.key,
+    ),
+    &[
+      payer_info.clone(),
+      vault_info.clone(),
+      system_program.clone(),
+    ],
+    &[vault_seeds],
+  )?;
+
+  invoke(
+    &spl_token_2022::instruction::initialize_account3(
+      token_program.key,
+      vault_info.key,
+      mint_info.key,
+      authority,
+    )?,
+    &[vault_info.clone(), mint_info.clone(), token_program.clone()],
+  )
+}
+
+/// Moves `amount` of collateral from `payer_info` into the market and returns the amount the
+/// market received. Token-2022 transfer fees are withheld from the amount the vault receives, so
+/// token markets credit the pools with the vault balance change rather than the requested amount.
+fn deposit_collateral<'a>(
+  payer_info: &AccountInfo<'a>,
+  prediction_market_info: &AccountInfo<'a>,
+  collateral: Option<&CollateralInfos<'_, 'a>>,
+  amount: u64,
+) -> Result<u64, ProgramError> {
+  match collateral {
+    Some(collateral) => {
+      let vault_amount = token_account_amount(collateral.vault)?;
+      invoke(
+        &spl_token_2022::instruction::transfer_checked(
+          collateral.token_program.key,
+          collateral.token_account.key,
+          collateral.mint.key,
+          collateral.vault.key,
+          payer_info.key,
+          &[],
+          amount,
+          mint_decimals(collateral.mint)?,
+        )?,
+        &[
+          collateral.token_account.clone(),
+          collateral.mint.clone(),
+          collateral.vault.clone(),
+          payer_info.clone(),
+          collateral.token_program.clone(),
+        ],
+      )?;
+      let received_amount = math::sub(token_account_amount(collateral.vault)?, vault_amount)?;
+      if received_amount != amount {
+        msg!("requested={},received={}", amount, received_amount);
+      }
+      Ok(received_amount)
+    }
+    None => {
+      invoke(
+        &system_instruction::transfer(payer_info.key, prediction_market_info.key, amount),
+        &[payer_info.clone(), prediction_market_info.clone()],
+      )?;
+      Ok(amount)
+    }
+  }
+}
+
+/// Resolution that claims are paid by. Nobody can take the losing pools of a parimutuel market
+/// whose winning side has no votes, so every prediction is refunded in full as on a tie.
+fn payout_resolution(market: &PredictionMarket) -> MarketResolution {
+  let winning_side_is_empty = match market.resolution {
+    MarketResolution::Yes => market.num_yes == 0,
+    MarketResolution::No => market.num_no == 0,
+    MarketResolution::Outcome(outcome) => market.outcome_votes[usize::from(outcome)] == 0,
+    MarketResolution::Scalar(_) => market.num_yes == 0 || market.num_no == 0,
+    MarketResolution::Unresolved | MarketResolution::Tie => false,
+  };
+  if market.pricing == MarketPricing::Parimutuel && winning_side_is_empty {
+    MarketResolution::Tie
+  } else {
+    market.resolution
+  }
+}
+
+/// Votes that are paid out of a resolved market and, for parimutuel markets, the pools they share.
+/// On a tie every vote takes its own stake back, otherwise the winning votes split both pools.
+fn payable_pool(
+  market: &PredictionMarket,
+  resolution: MarketResolution,
+) -> Result<(u64, u64), HitMyBetError> {
+  let binary_pool = math::add(market.balance_yes, market.balance_no)?;
+  let outcome_pool = math::sum(market.outcome_balances)?;
+  match resolution {
+    MarketResolution::Yes => Ok((market.num_yes, binary_pool)),
+    MarketResolution::No => Ok((market.num_no, binary_pool)),
+    MarketResolution::Outcome(outcome) => {
+      Ok((market.outcome_votes[usize::from(outcome)], outcome_pool))
+    }
+    MarketResolution::Scalar(_) => Ok((math::add(market.num_yes, market.num_no)?, binary_pool)),
+    MarketResolution::Tie => Ok((
+      math::sum(
+        [market.num_yes, market.num_no]
+          .into_iter()
+          .chain(market.outcome_votes),
+      )?,
+      math::add(binary_pool, outcome_pool)?,
+    )),
+    MarketResolution::Unresolved => Ok((0, 0)),
+  }
+}
+
+/// Pays `amount` of collateral out of the market: from the vault for token markets, or straight
+/// from the market account lamports for SOL markets.
+fn pay_out<'a>(
+  market: &PredictionMarket,
+  prediction_market_info: &AccountInfo<'a>,
+  recipient_info: &AccountInfo<'a>,
+  collateral: Option<&CollateralInfos<'_, 'a>>,
+  amount: u64,
+) -> ProgramResult {
+  match collateral {
+    Some(collateral) => transfer_from_vault(market, prediction_market_info, collateral, amount),
+    None => {
+      let dest_starting_lamports = recipient_info.lamports();
+      let market_starting_lamports = prediction_market_info.lamports();
+      **recipient_info.lamports.borrow_mut() = math::add(dest_starting_lamports, amount)?;
+      **prediction_market_info.lamports.borrow_mut() = math::sub(market_starting_lamports, amount)?;
+      Ok(())
+    }
+  }
+}
+
+fn mint_shares<'a>(
+  market: &PredictionMarket,
+  prediction_market_info: &AccountInfo<'a>,
+  shares: &ShareInfos<'_, 'a>,
+  amount: u64,
+) -> ProgramResult {
+  invoke_signed(
+    &spl_token::instruction::mint_to(
+      shares.token_program.key,
+      shares.share_mint.key,
+      shares.token_account.key,
+      prediction_market_info.key,
+      &[],
+      amount,
+    )?,
+    &[
+      shares.share_mint.clone(),
+      shares.token_account.clone(),
+      prediction_market_info.clone(),
+      shares.token_program.clone(),
+    ],
+    &[&[
+      PredictionMarket::SEED_PREFIX.as_bytes(),
+      market.market_id.as_ref(),
+      &[market.bump_seed],
+    ]],
+  )
+}
+
+/// Removes the market as mint authority of a share mint, so no shares can be minted anymore.
+fn revoke_share_mint_authority<'a>(
+  market: &PredictionMarket,
+  prediction_market_info: &AccountInfo<'a>,
+  share_mint_info: &AccountInfo<'a>,
+  token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+  invoke_signed(
+    &spl_token::instruction::set_authority(
+      token_program.key,
+      share_mint_info.key,
+      None,
+      spl_token::instruction::AuthorityType::MintTokens,
+      prediction_market_info.key,
+      &[],
+    )?,
+    &[
+      share_mint_info.clone(),
+      prediction_market_info.clone(),
+      token_program.clone(),
+    ],
+    &[&[
+      PredictionMarket::SEED_PREFIX.as_bytes(),
+      market.market_id.as_ref(),
+      &[market.bump_seed],
+    ]],
+  )
+}
+
+fn burn_shares<'a>(
+  owner_info: &AccountInfo<'a>,
+  shares: &ShareInfos<'_, 'a>,
+  amount: u64,
+) -> ProgramResult {
+  invoke(
+    &spl_token::instruction::burn(
+      shares.token_program.key,
+      shares.token_account.key,
+      shares.share_mint.key,
+      owner_info.key,
+      &[],
+      amount,
+    )?,
+    &[
+      shares.token_account.clone(),
+      shares.share_mint.clone(),
+      owner_info.clone(),
+      shares.token_program.clone(),
+    ],
+  )
+}
+
+fn transfer_from_vault<'a>(
+  market: &PredictionMarket,
+  prediction_market_info: &AccountInfo<'a>,
+  collateral: &CollateralInfos<'_, 'a>,
+  amount: u64,
+) -> ProgramResult {
+  invoke_signed(
+    &spl_token_2022::instruction::transfer_checked(
+      collateral.token_program.key,
+      collateral.vault.key,
+      collateral.mint.key,
+      collateral.token_account.key,
+      prediction_market_info.key,
+      &[],
+      amount,
+      mint_decimals(collateral.mint)?,
+    )?,
+    &[
+      collateral.vault.clone(),
+      collateral.mint.clone(),
+      collateral.token_account.clone(),
+      prediction_market_info.clone(),
+      collateral.token_program.clone(),
+    ],
+    &[&[
+      PredictionMarket::SEED_PREFIX.as_bytes(),
+      market.market_id.as_ref(),
+      &[market.bump_seed],
+    ]],
+  )
+}
+
+/// Closes the market vault. Token-2022 only closes accounts without withheld transfer fees, so
+/// the fees withheld in the vault are first harvested to the mint.
+fn close_market_vault<'a>(
+  market: &PredictionMarket,
+  prediction_market_info: &AccountInfo<'a>,
+  collateral: &CollateralInfos<'_, 'a>,
+  recipient_info: &AccountInfo<'a>,
+) -> ProgramResult {
+  if mint_has_transfer_fee(collateral.mint)? {
+    invoke(
+      &spl_token_2022::extension::transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+        collateral.token_program.key,
+        collateral.mint.key,
+        &[collateral.vault.key],
+      )?,
+      &[
+        collateral.mint.clone(),
+        collateral.vault.clone(),
+        collateral.token_program.clone(),
+      ],
+    )?;
+  }
+  invoke_signed(
+    &spl_token_2022::instruction::close_account(
+      collateral.token_program.key,
+      collateral.vault.key,
+      recipient_info.key,
+      prediction_market_info.key,
+      &[],
+    )?,
+    &[
+      collateral.vault.clone(),
+      recipient_info.clone(),
+      prediction_market_info.clone(),
+      collateral.token_program.clone(),
+    ],
+    &[&[
+      PredictionMarket::SEED_PREFIX.as_bytes(),
+      market.market_id.as_ref(),
+      &[market.bump_seed],
+    ]],
+  )
+}
+
+fn mint_has_transfer_fee(mint_info: &AccountInfo) -> Result<bool, ProgramError> {
+  let mint_data = mint_info.data.borrow();
+  Ok(
+    StateWithExtensions::<Mint>::unpack(&mint_data)?
+      .get_extension_types()?
+      .contains(&ExtensionType::TransferFeeConfig),
+  )
+}
+
+fn mint_decimals(mint_info: &AccountInfo) -> Result<u8, ProgramError> {
+  let mint_data = mint_info.data.borrow();
+  Ok(
+    StateWithExtensions::<Mint>::unpack(&mint_data)?
+      .base
+      .decimals,
+  )
+}
+
+fn token_account_amount(token_account_info: &AccountInfo) -> Result<u64, ProgramError> {
+  let account_data = token_account_info.data.borrow();
+  Ok(
+    StateWithExtensions::<Account>::unpack(&account_data)?
+      .base
+      .amount,
+  )
+}
+
+fn token_account_owner(token_account_info: &AccountInfo) -> Result<Pubkey, ProgramError> {
+  let account_data = token_account_info.data.borrow();
+  Ok(
+    StateWithExtensions::<Account>::unpack(&account_data)?
+      .base
+      .owner,
+  )
+}
+
 fn assert_market_owner(program_id: &Pubkey) -> ProgramResult {
   if !crate::check_id(program_id) {
     Err(HitMyBetError::InvalidMarketOwner.into())
@@ -483,6 +4389,14 @@ fn assert_system_program(program_id: &Pubkey) -> ProgramResult {
   }
 }
 
+fn assert_token_program(program_id: &Pubkey) -> ProgramResult {
+  if !spl_token::check_id(program_id) && !spl_token_2022::check_id(program_id) {
+    Err(HitMyBetError::IncorrectProgramId.into())
+  } else {
+    Ok(())
+  }
+}
+
 // fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> ProgramResult {
 //   if !rent.is_exempt(account_info.lamports(), account_info.data_len()) {
 //     msg!(&rent.minimum_balance(account_info.data_len()).to_string());
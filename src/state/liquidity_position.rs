@@ -0,0 +1,41 @@
+use crate::state::PROGRAM_VERSION;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Liquidity provider stake in a constant-product market. When liquidity is added to or removed
+/// from an unbalanced pool, the outcome exposure that does not fit the pool ratio is kept here in
+/// payout units (lamports or collateral base units) and paid out at claim.
+#[derive(BorshSerialize, BorshDeserialize, Default, PartialEq, Debug)]
+pub struct LiquidityPosition {
+  pub version: u8,
+  pub bump_seed: u8,
+  pub lp_shares: u64,
+  pub excess_yes: u64,
+  pub excess_no: u64,
+}
+
+impl LiquidityPosition {
+  pub const LEN: usize = core::mem::size_of::<LiquidityPosition>();
+
+  pub const SEED_PREFIX: &'static str = "liquidity_position";
+
+  /// Position of `provider` in `market`.
+  pub fn find_address(program_id: &Pubkey, market: &Pubkey, provider: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+      &[
+        Self::SEED_PREFIX.as_bytes(),
+        market.as_ref(),
+        provider.as_ref(),
+      ],
+      program_id,
+    )
+  }
+
+  pub fn with_seed(bump_seed: u8) -> LiquidityPosition {
+    LiquidityPosition {
+      version: PROGRAM_VERSION,
+      bump_seed,
+      ..Default::default()
+    }
+  }
+}
@@ -1,13 +1,22 @@
+mod liquidity_position;
+mod order_book;
 mod prediction_market;
+mod program_config;
+mod referrer_account;
+mod resolver_set;
+mod treasury;
 mod user_prediction;
 
-use solana_program::native_token::LAMPORTS_PER_SOL;
+pub use liquidity_position::*;
+pub use order_book::*;
 pub use prediction_market::*;
+pub use program_config::*;
+pub use referrer_account::*;
+pub use resolver_set::*;
+pub use treasury::*;
 pub use user_prediction::*;
 
 pub const UNINITIALIZED_VERSION: u8 = 0;
-pub const PROGRAM_VERSION: u8 = 1;
-
-pub const CREATE_MARKET_FEE: u64 = LAMPORTS_PER_SOL / 10;
-
-pub const VOTE_PRICE: u64 = LAMPORTS_PER_SOL / 10;
+/// Version of the instruction and account layouts, bumped whenever either changes. Instructions
+/// of another version fail with `VersionMismatch`.
+pub const PROGRAM_VERSION: u8 = 2;
@@ -0,0 +1,77 @@
+use crate::state::PROGRAM_VERSION;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+pub const MAX_ORDERS: usize = 32;
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub enum OrderSide {
+  /// Buys YES shares; `price * quantity` of collateral is escrowed in the market.
+  Bid,
+  /// Sells YES shares; the shares are burned while the order is open and minted back on cancel.
+  Ask,
+}
+
+/// Resting limit order for YES shares. `price` is in lamports or collateral base units per share.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Order {
+  pub order_id: u64,
+  pub owner: Pubkey,
+  pub side: OrderSide,
+  pub price: u64,
+  pub quantity: u64,
+}
+
+impl Order {
+  pub const LEN: usize = core::mem::size_of::<Order>();
+}
+
+/// Slab of open orders of a market, allocated for [`MAX_ORDERS`] orders. Orders are kept in
+/// placement order, so `order_id` gives time priority between orders at the same price.
+#[derive(BorshSerialize, BorshDeserialize, Default, PartialEq, Debug)]
+pub struct OrderBook {
+  pub version: u8,
+  pub bump_seed: u8,
+  pub next_order_id: u64,
+  pub orders: Vec<Order>,
+}
+
+impl OrderBook {
+  pub const LEN: usize = 2 + 8 + 4 + MAX_ORDERS * Order::LEN;
+
+  pub const SEED_PREFIX: &'static str = "order_book";
+
+  pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[Self::SEED_PREFIX.as_bytes(), market.as_ref()], program_id)
+  }
+
+  pub fn with_seed(bump_seed: u8) -> OrderBook {
+    OrderBook {
+      version: PROGRAM_VERSION,
+      bump_seed,
+      ..Default::default()
+    }
+  }
+
+  /// Index of the highest bid, the earliest one on equal prices.
+  pub fn best_bid(&self) -> Option<usize> {
+    self.best(OrderSide::Bid, |order| {
+      (u64::MAX - order.price, order.order_id)
+    })
+  }
+
+  /// Index of the lowest ask, the earliest one on equal prices.
+  pub fn best_ask(&self) -> Option<usize> {
+    self.best(OrderSide::Ask, |order| (order.price, order.order_id))
+  }
+
+  fn best(&self, side: OrderSide, key: impl Fn(&Order) -> (u64, u64)) -> Option<usize> {
+    self
+      .orders
+      .iter()
+      .enumerate()
+      .filter(|(_, order)| order.side == side)
+      .min_by_key(|(_, order)| key(order))
+      .map(|(index, _)| index)
+  }
+}
@@ -1,27 +1,122 @@
-use crate::state::PROGRAM_VERSION;
+use crate::state::{UserVote, PROGRAM_VERSION};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::clock::UnixTimestamp;
 use solana_program::pubkey::Pubkey;
 
+pub const MAX_OUTCOMES: usize = 8;
+
 #[derive(BorshSerialize, BorshDeserialize, Default, PartialEq, Debug)]
 pub struct PredictionMarket {
   pub version: u8,
   pub bump_seed: u8,
+  pub market_id: [u8; 16],
+  pub kind: MarketKind,
+  pub pricing: MarketPricing,
+  pub collateral_mint: Option<Pubkey>,
+  pub creator: Pubkey,
   pub resolver: Pubkey,
+  pub pending_resolver: Option<Pubkey>,
+  /// Whether the market is resolved by the quorum of its `ResolverSet` instead of `resolver`.
+  pub has_resolver_set: bool,
+  /// Price condition resolving the market with `ResolveFromOracle` instead of `resolver`.
+  pub oracle: Option<OracleCondition>,
   pub num_yes: u64,
   pub num_no: u64,
   pub balance_yes: u64,
   pub balance_no: u64,
+  pub outcome_votes: [u64; MAX_OUTCOMES],
+  pub outcome_balances: [u64; MAX_OUTCOMES],
+  pub reserve_yes: u64,
+  pub reserve_no: u64,
+  pub lp_supply: u64,
+  pub vote_price: u64,
+  pub min_bet: u64,
+  pub max_bet: u64,
+  pub protocol_fee_bps: u16,
+  pub creator_fee_bps: u16,
+  /// Share of each referred bet paid to the referrer on top of the bet.
+  pub referral_fee_bps: u16,
+  /// Creator fees accrued on winnings and not withdrawn yet.
+  pub creator_fees: u64,
   pub resolution: MarketResolution,
+  pub resolution_status: ResolutionStatus,
   pub open_until: UnixTimestamp,
+  /// How long a resolution by the resolver can be disputed before it becomes final, 0 for none.
+  pub dispute_period: UnixTimestamp,
+  /// Set when the resolver proposes a resolution and there is a dispute period.
+  pub dispute_deadline: UnixTimestamp,
+  /// Lamports that anyone but the resolver posts to propose a resolution, and that a challenger
+  /// posts to dispute one. 0 leaves resolution to the resolver.
+  pub resolution_bond: u64,
+  /// Signer of the current resolution, the resolver included.
+  pub proposer: Option<Pubkey>,
+  /// Bond held for the proposer, 0 when the resolver resolved the market.
+  pub proposer_bond: u64,
+  pub challenger: Option<Pubkey>,
+  /// Bond held for the challenger until the admin settles the dispute.
+  pub challenger_bond: u64,
+  /// How long claims stay open once the resolution is final, 0 for no limit.
+  pub claim_period: UnixTimestamp,
+  /// Set at resolution when there is a claim period. Once it passes, claims are rejected and the
+  /// unclaimed remainder can be swept to the treasury.
+  pub claim_deadline: UnixTimestamp,
+  /// Whether the unclaimed remainder has been swept to the treasury.
+  pub swept: bool,
+  /// Collateral paid out of the pools by `ClaimMarket` so far, fees included.
+  pub total_claimed: u64,
+  /// Winning votes paid out so far. On parimutuel markets the claim that settles the last of them
+  /// also takes the rounding remainder, so the payable pools end up empty.
+  pub claimed_votes: u64,
+  /// Liquidity positions that have not been claimed yet.
+  pub open_positions: u64,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, PartialEq, Debug)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Default, PartialEq, Debug)]
 pub enum MarketResolution {
+  #[default]
   Unresolved,
   Tie,
   Yes,
   No,
+  Outcome(u8),
+  Scalar(i64),
+}
+
+/// Whether the `resolution` of a market can still change. A resolution by the resolver is
+/// `Proposed` until the dispute period ends, when it becomes final without further action. A
+/// disputed resolution waits for the program config admin to settle it.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Default, PartialEq, Debug)]
+pub enum ResolutionStatus {
+  #[default]
+  Final,
+  Proposed,
+  UnderDispute,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default, Clone, Copy, PartialEq, Debug)]
+pub enum MarketKind {
+  #[default]
+  Binary,
+  Categorical {
+    num_outcomes: u8,
+  },
+  Scalar {
+    min: i64,
+    max: i64,
+  },
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default, Clone, Copy, PartialEq, Debug)]
+pub enum MarketPricing {
+  /// Every vote costs `vote_price` and the winning side splits the pools.
+  #[default]
+  Parimutuel,
+  /// Logarithmic Market Scoring Rule with liquidity parameter `b` counted in shares. Each winning
+  /// share pays `vote_price`.
+  Lmsr { liquidity: u64 },
+  /// Constant-product pool of YES/NO reserves funded by liquidity providers. Reserves are kept in
+  /// payout units and each winning share pays `vote_price`.
+  Cpmm,
 }
 
 impl PredictionMarket {
@@ -29,27 +124,176 @@ impl PredictionMarket {
 
   pub const SEED_PREFIX: &'static str = "prediction_market";
 
+  pub const VAULT_SEED_PREFIX: &'static str = "market_vault";
+
+  pub const SHARE_MINT_SEED_PREFIX: &'static str = "share_mint";
+
+  /// Fee kept in the pool when votes of a parimutuel market are sold back, in basis points.
+  pub const EXIT_FEE_BPS: u64 = 200;
+
+  /// Outcome share mint of `side` (see [`MarketKind::num_sides`]), minted and burned by the market.
+  pub fn find_share_mint_address(program_id: &Pubkey, market: &Pubkey, side: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+      &[
+        Self::SHARE_MINT_SEED_PREFIX.as_bytes(),
+        market.as_ref(),
+        &[side],
+      ],
+      program_id,
+    )
+  }
+
   pub fn with_params(params: InitPredictionMarketParams) -> PredictionMarket {
     PredictionMarket {
       version: PROGRAM_VERSION,
       bump_seed: params.bump_seed,
+      market_id: params.market_id,
+      kind: params.kind,
+      pricing: params.pricing,
+      collateral_mint: params.collateral_mint,
+      creator: params.creator,
       resolver: params.resolver,
+      has_resolver_set: params.has_resolver_set,
+      oracle: params.oracle,
+      vote_price: params.limits.vote_price,
+      min_bet: params.limits.min_bet,
+      max_bet: params.limits.max_bet,
+      protocol_fee_bps: params.fees.protocol_fee_bps,
+      creator_fee_bps: params.fees.creator_fee_bps,
+      referral_fee_bps: params.fees.referral_fee_bps,
       open_until: params.open_until,
+      dispute_period: params.dispute_period,
+      resolution_bond: params.resolution_bond,
+      claim_period: params.claim_period,
       ..Default::default()
     }
   }
 }
 
+/// Unit price of a vote or share and the bounds of a single bet, both in lamports or collateral
+/// base units. A `max_bet` of 0 leaves bets unbounded.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub struct BetLimits {
+  pub vote_price: u64,
+  pub min_bet: u64,
+  pub max_bet: u64,
+}
+
+impl BetLimits {
+  pub fn is_valid(&self) -> bool {
+    self.vote_price > 0 && (self.max_bet == 0 || self.min_bet <= self.max_bet)
+  }
+
+  pub fn contains(&self, bet_amount: u64) -> bool {
+    bet_amount >= self.min_bet && (self.max_bet == 0 || bet_amount <= self.max_bet)
+  }
+}
+
+/// Market fees in basis points. The protocol and creator fees are taken from the winnings of
+/// every claim, refunded stakes are not charged; scalar winnings are the payout above the stake.
+/// Market maker pricing takes neither. The referral fee is paid by bettors on top of referred
+/// bets.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Default, PartialEq, Debug)]
+pub struct MarketFees {
+  pub protocol_fee_bps: u16,
+  pub creator_fee_bps: u16,
+  pub referral_fee_bps: u16,
+}
+
+impl MarketFees {
+  /// Upper bound of the protocol and creator fees together.
+  pub const MAX_TOTAL_BPS: u16 = 1_000;
+
+  /// Upper bound of the referral fee.
+  pub const MAX_REFERRAL_BPS: u16 = 500;
+
+  pub fn is_valid(&self) -> bool {
+    u32::from(self.protocol_fee_bps) + u32::from(self.creator_fee_bps)
+      <= u32::from(Self::MAX_TOTAL_BPS)
+      && self.referral_fee_bps <= Self::MAX_REFERRAL_BPS
+  }
+}
+
+/// Resolves a binary market yes if the price of `price_feed` at `resolve_at` compares to
+/// `threshold * 10^exponent` as `comparison` says, and no otherwise.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub struct OracleCondition {
+  pub price_feed: Pubkey,
+  /// Id of the price feed the account must hold, as price update accounts can be reused for
+  /// other feeds.
+  pub feed_id: [u8; 32],
+  pub comparison: PriceComparison,
+  pub threshold: i64,
+  /// Exponent of the feed's prices, which `threshold` is expressed in.
+  pub exponent: i32,
+  pub resolve_at: UnixTimestamp,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub enum PriceComparison {
+  Above,
+  AboveOrEqual,
+  Below,
+  BelowOrEqual,
+}
+
+impl OracleCondition {
+  pub fn is_met(&self, price: i64) -> bool {
+    match self.comparison {
+      PriceComparison::Above => price > self.threshold,
+      PriceComparison::AboveOrEqual => price >= self.threshold,
+      PriceComparison::Below => price < self.threshold,
+      PriceComparison::BelowOrEqual => price <= self.threshold,
+    }
+  }
+}
+
+impl PredictionMarket {
+  pub fn limits(&self) -> BetLimits {
+    BetLimits {
+      vote_price: self.vote_price,
+      min_bet: self.min_bet,
+      max_bet: self.max_bet,
+    }
+  }
+}
+
+impl MarketKind {
+  /// Number of share mints a market owns: yes/no (long/short) for binary and scalar markets, one
+  /// per outcome for categorical markets.
+  pub fn num_sides(&self) -> u8 {
+    match self {
+      MarketKind::Binary | MarketKind::Scalar { .. } => 2,
+      MarketKind::Categorical { num_outcomes } => *num_outcomes,
+    }
+  }
+
+  pub fn side_vote(&self, side: u8) -> UserVote {
+    match (self, side) {
+      (MarketKind::Categorical { .. }, outcome) => UserVote::Outcome(outcome),
+      (_, 0) => UserVote::Yes,
+      (_, 1) => UserVote::No,
+      _ => UserVote::Unspecified,
+    }
+  }
+}
+
 pub struct InitPredictionMarketParams {
   pub bump_seed: u8,
+  pub market_id: [u8; 16],
+  pub kind: MarketKind,
+  pub pricing: MarketPricing,
+  pub collateral_mint: Option<Pubkey>,
+  pub creator: Pubkey,
   pub resolver: Pubkey,
+  pub has_resolver_set: bool,
+  pub oracle: Option<OracleCondition>,
+  pub limits: BetLimits,
+  pub fees: MarketFees,
   pub open_until: UnixTimestamp,
-}
-
-impl Default for MarketResolution {
-  fn default() -> MarketResolution {
-    MarketResolution::Unresolved
-  }
+  pub dispute_period: UnixTimestamp,
+  pub resolution_bond: u64,
+  pub claim_period: UnixTimestamp,
 }
 
 // impl Sized for PredictionMarket {
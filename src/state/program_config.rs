@@ -0,0 +1,37 @@
+use crate::state::PROGRAM_VERSION;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+#[derive(BorshSerialize, BorshDeserialize, Default, PartialEq, Debug)]
+pub struct ProgramConfig {
+  pub version: u8,
+  pub bump_seed: u8,
+  pub admin: Pubkey,
+  pub pending_admin: Option<Pubkey>,
+  pub create_market_fee: u64,
+  pub vote_price: u64,
+}
+
+impl ProgramConfig {
+  pub const LEN: usize = core::mem::size_of::<ProgramConfig>();
+
+  pub const SEED_PREFIX: &'static str = "program_config";
+
+  pub fn with_params(params: InitProgramConfigParams) -> ProgramConfig {
+    ProgramConfig {
+      version: PROGRAM_VERSION,
+      bump_seed: params.bump_seed,
+      admin: params.admin,
+      pending_admin: None,
+      create_market_fee: params.create_market_fee,
+      vote_price: params.vote_price,
+    }
+  }
+}
+
+pub struct InitProgramConfigParams {
+  pub bump_seed: u8,
+  pub admin: Pubkey,
+  pub create_market_fee: u64,
+  pub vote_price: u64,
+}
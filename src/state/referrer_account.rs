@@ -0,0 +1,95 @@
+use crate::state::PROGRAM_VERSION;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Referral earnings of a partner frontend. Referral fees of native SOL markets are held by this
+/// account; token collateral is held by one referrer vault per mint, owned by this account, and
+/// counted in the `ReferrerMintFees` of the mint. Withdrawals are made by the referrer.
+#[derive(BorshSerialize, BorshDeserialize, Default, PartialEq, Debug)]
+pub struct ReferrerAccount {
+  pub version: u8,
+  pub bump_seed: u8,
+  pub referrer: Pubkey,
+  /// Predictions made with this referrer so far, on any market.
+  pub num_referrals: u64,
+  /// Referral fees of native SOL markets received so far, in lamports.
+  pub total_fees: u64,
+  /// Referral fees of native SOL markets withdrawn so far, in lamports.
+  pub withdrawn_fees: u64,
+}
+
+impl ReferrerAccount {
+  pub const LEN: usize = core::mem::size_of::<ReferrerAccount>();
+
+  pub const SEED_PREFIX: &'static str = "referrer";
+
+  pub const VAULT_SEED_PREFIX: &'static str = "referrer_vault";
+
+  pub fn find_address(program_id: &Pubkey, referrer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+      &[Self::SEED_PREFIX.as_bytes(), referrer.as_ref()],
+      program_id,
+    )
+  }
+
+  /// Token account of `referrer` for collateral `mint`.
+  pub fn find_vault_address(program_id: &Pubkey, referrer: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+      &[
+        Self::VAULT_SEED_PREFIX.as_bytes(),
+        referrer.as_ref(),
+        mint.as_ref(),
+      ],
+      program_id,
+    )
+  }
+
+  pub fn with_params(bump_seed: u8, referrer: Pubkey) -> ReferrerAccount {
+    ReferrerAccount {
+      version: PROGRAM_VERSION,
+      bump_seed,
+      referrer,
+      ..Default::default()
+    }
+  }
+}
+
+/// Referral fees of a referrer in one token mint, held by its referrer vault of the mint.
+#[derive(BorshSerialize, BorshDeserialize, Default, PartialEq, Debug)]
+pub struct ReferrerMintFees {
+  pub version: u8,
+  pub bump_seed: u8,
+  pub referrer: Pubkey,
+  pub mint: Pubkey,
+  /// Referral fees received by the referrer vault so far, in base units of the mint.
+  pub total_fees: u64,
+  /// Referral fees withdrawn from the referrer vault so far, in base units of the mint.
+  pub withdrawn_fees: u64,
+}
+
+impl ReferrerMintFees {
+  pub const LEN: usize = core::mem::size_of::<ReferrerMintFees>();
+
+  pub const SEED_PREFIX: &'static str = "referrer_fees";
+
+  pub fn find_address(program_id: &Pubkey, referrer: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+      &[
+        Self::SEED_PREFIX.as_bytes(),
+        referrer.as_ref(),
+        mint.as_ref(),
+      ],
+      program_id,
+    )
+  }
+
+  pub fn with_params(bump_seed: u8, referrer: Pubkey, mint: Pubkey) -> ReferrerMintFees {
+    ReferrerMintFees {
+      version: PROGRAM_VERSION,
+      bump_seed,
+      referrer,
+      mint,
+      ..Default::default()
+    }
+  }
+}
@@ -0,0 +1,105 @@
+use crate::state::{MarketResolution, PROGRAM_VERSION};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::clock::UnixTimestamp;
+use solana_program::pubkey::Pubkey;
+
+pub const MAX_RESOLVERS: usize = 8;
+
+/// Independent resolvers of a market and their votes. The market resolves once `threshold` votes
+/// agree; when the remaining votes can no longer get any resolution there, the set is deadlocked
+/// and the program config admin resolves the market instead. The admin also steps in when the set
+/// has not resolved the market `RESOLUTION_TIMEOUT` seconds after betting closed.
+#[derive(BorshSerialize, BorshDeserialize, Default, PartialEq, Debug)]
+pub struct ResolverSet {
+  pub version: u8,
+  pub bump_seed: u8,
+  pub threshold: u8,
+  pub num_resolvers: u8,
+  pub resolvers: [Pubkey; MAX_RESOLVERS],
+  pub votes: [Option<MarketResolution>; MAX_RESOLVERS],
+}
+
+impl ResolverSet {
+  pub const LEN: usize = core::mem::size_of::<ResolverSet>();
+
+  pub const SEED_PREFIX: &'static str = "resolver_set";
+
+  pub const RESOLUTION_TIMEOUT: UnixTimestamp = 7 * 24 * 60 * 60;
+
+  pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[Self::SEED_PREFIX.as_bytes(), market.as_ref()], program_id)
+  }
+
+  pub fn with_params(bump_seed: u8, params: &ResolverSetParams) -> ResolverSet {
+    let mut resolvers = [Pubkey::default(); MAX_RESOLVERS];
+    resolvers[..params.resolvers.len()].copy_from_slice(&params.resolvers);
+    ResolverSet {
+      version: PROGRAM_VERSION,
+      bump_seed,
+      threshold: params.threshold,
+      num_resolvers: u8::try_from(params.resolvers.len()).expect("resolver count"),
+      resolvers,
+      ..Default::default()
+    }
+  }
+
+  /// Index of `resolver` in the set.
+  pub fn position(&self, resolver: &Pubkey) -> Option<usize> {
+    self.resolvers[..usize::from(self.num_resolvers)]
+      .iter()
+      .position(|key| key.eq(resolver))
+  }
+
+  fn tally(&self, resolution: &MarketResolution) -> usize {
+    self
+      .votes
+      .iter()
+      .filter(|vote| vote.as_ref() == Some(resolution))
+      .count()
+  }
+
+  /// Resolution that at least `threshold` resolvers voted for.
+  pub fn quorum(&self) -> Option<MarketResolution> {
+    self
+      .votes
+      .iter()
+      .flatten()
+      .find(|resolution| self.tally(resolution) >= usize::from(self.threshold))
+      .copied()
+  }
+
+  /// Whether no resolution can reach the threshold anymore, even with every missing vote.
+  pub fn is_deadlocked(&self) -> bool {
+    let missing = usize::from(self.num_resolvers) - self.votes.iter().flatten().count();
+    let leading = self
+      .votes
+      .iter()
+      .flatten()
+      .map(|resolution| self.tally(resolution))
+      .max()
+      .unwrap_or(0);
+    leading + missing < usize::from(self.threshold)
+  }
+}
+
+/// Resolvers and threshold of a market resolved by quorum.
+#[derive(BorshDeserialize, BorshSerialize, Clone, PartialEq, Debug)]
+pub struct ResolverSetParams {
+  pub resolvers: Vec<Pubkey>,
+  pub threshold: u8,
+}
+
+impl ResolverSetParams {
+  pub fn is_valid(&self) -> bool {
+    let num_resolvers = self.resolvers.len();
+    let distinct = self
+      .resolvers
+      .iter()
+      .enumerate()
+      .all(|(index, resolver)| !self.resolvers[..index].contains(resolver));
+    num_resolvers <= MAX_RESOLVERS
+      && self.threshold > 0
+      && usize::from(self.threshold) <= num_resolvers
+      && distinct
+  }
+}
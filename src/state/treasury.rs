@@ -0,0 +1,43 @@
+use crate::state::PROGRAM_VERSION;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Protocol treasury. Market creation fees and lamports swept from markets are held by this
+/// account; token collateral is held by one treasury vault per mint, owned by this account.
+/// Withdrawals are made by the program config admin.
+#[derive(BorshSerialize, BorshDeserialize, Default, PartialEq, Debug)]
+pub struct Treasury {
+  pub version: u8,
+  pub bump_seed: u8,
+  /// Lamports received so far: market creation fees, and the protocol fees, swept winnings and
+  /// forfeited resolution bonds of native SOL markets. Token revenue is only held by the vaults.
+  pub total_fees: u64,
+}
+
+impl Treasury {
+  pub const LEN: usize = core::mem::size_of::<Treasury>();
+
+  pub const SEED_PREFIX: &'static str = "treasury";
+
+  pub const VAULT_SEED_PREFIX: &'static str = "treasury_vault";
+
+  pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[Self::SEED_PREFIX.as_bytes()], program_id)
+  }
+
+  /// Token account of the treasury for collateral `mint`.
+  pub fn find_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+      &[Self::VAULT_SEED_PREFIX.as_bytes(), mint.as_ref()],
+      program_id,
+    )
+  }
+
+  pub fn with_seed(bump_seed: u8) -> Treasury {
+    Treasury {
+      version: PROGRAM_VERSION,
+      bump_seed,
+      ..Default::default()
+    }
+  }
+}
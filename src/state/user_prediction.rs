@@ -1,4 +1,4 @@
-use crate::state::PROGRAM_VERSION;
+use crate::state::{MAX_OUTCOMES, PROGRAM_VERSION};
 use borsh::{BorshDeserialize, BorshSerialize};
 
 #[derive(BorshSerialize, BorshDeserialize, Default, Debug)]
@@ -7,6 +7,7 @@ pub struct UserPrediction {
   pub bump_seed: u8,
   pub num_votes_yes: u64,
   pub num_votes_no: u64,
+  pub outcome_votes: [u64; MAX_OUTCOMES],
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -14,6 +15,35 @@ pub enum UserVote {
   Unspecified,
   Yes,
   No,
+  Outcome(u8),
+}
+
+impl UserVote {
+  /// Share mint side of the vote, matching [`crate::state::MarketKind::side_vote`].
+  pub fn side(&self) -> Option<u8> {
+    match self {
+      UserVote::Unspecified => None,
+      UserVote::Yes => Some(0),
+      UserVote::No => Some(1),
+      UserVote::Outcome(outcome) => Some(*outcome),
+    }
+  }
+}
+
+/// Long positions are accounted in the yes pools and short positions in the no pools.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub enum ScalarPosition {
+  Long,
+  Short,
+}
+
+impl From<ScalarPosition> for UserVote {
+  fn from(value: ScalarPosition) -> Self {
+    match value {
+      ScalarPosition::Long => UserVote::Yes,
+      ScalarPosition::Short => UserVote::No,
+    }
+  }
 }
 
 impl UserPrediction {
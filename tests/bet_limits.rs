@@ -0,0 +1,376 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config};
+use helpers::token::{create_share_account, share_mint_pubkey};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  buy_shares, claim_market, init_prediction_market, make_prediction, mint_complete_set,
+  place_order, resolve_market, CompleteSetAccounts, MarketParams, UserShareAccounts,
+};
+use hit_my_bet::state::{
+  BetLimits, MarketKind, MarketPricing, MarketResolution, OrderSide, PredictionMarket, UserVote,
+};
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+// One lamport per vote, so bets can be any amount within the bounds.
+const LIMITS: BetLimits = BetLimits {
+  vote_price: 1,
+  min_bet: 1_000,
+  max_bet: LAMPORTS_PER_SOL,
+};
+
+#[tokio::test]
+async fn test_bet_limits() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  for (limits, is_ok) in [
+    (
+      BetLimits {
+        min_bet: LIMITS.max_bet + 1,
+        ..LIMITS
+      },
+      false,
+    ),
+    (
+      BetLimits {
+        vote_price: 0,
+        ..LIMITS
+      },
+      false,
+    ),
+    (LIMITS, true),
+  ] {
+    let instruction = init_prediction_market(
+      hit_my_bet::ID,
+      payer.pubkey(),
+      market_pubkey,
+      resolver_kp.pubkey(),
+      config_pubkey(),
+      *MARKET_ID,
+      timestamp + 60,
+      MarketKind::Binary,
+      MarketPricing::Parimutuel,
+      MarketParams {
+        limits: Some(limits),
+        ..Default::default()
+      },
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&payer, &resolver_kp],
+    )
+    .await;
+    if is_ok {
+      assert!(transaction_result.is_ok());
+    } else {
+      assert_eq!(
+        transaction_result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+          0,
+          InstructionError::Custom(HitMyBetError::InvalidBetLimits.into())
+        )
+      );
+    }
+  }
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.limits(), LIMITS);
+
+  let mut voters = Vec::new();
+  for _ in 0..2 {
+    voters.push(
+      funded_keypair(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &payer,
+        10 * LAMPORTS_PER_SOL,
+      )
+      .await,
+    );
+  }
+
+  for (voter_kp, vote, num_votes, is_ok) in [
+    (&voters[0], UserVote::Yes, LIMITS.min_bet - 1, false),
+    (&voters[0], UserVote::Yes, LIMITS.max_bet + 1, false),
+    (&voters[0], UserVote::Yes, 70_001, true),
+    (&voters[1], UserVote::No, 30_003, true),
+  ] {
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      vote,
+      num_votes,
+      None,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[voter_kp],
+    )
+    .await;
+    if is_ok {
+      assert!(transaction_result.is_ok());
+    } else {
+      assert_eq!(
+        transaction_result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+          0,
+          InstructionError::Custom(HitMyBetError::BetOutOfBounds.into())
+        )
+      );
+    }
+  }
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // The only winner takes the whole losing pool even though it does not divide by their votes.
+  let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    voters[0].pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voters[0].pubkey()),
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voters[0]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+  assert_eq!(balance_before - balance_after, 70_001 + 30_003);
+}
+
+/// Limits of the market maker markets, whose shares cost a fraction of their vote price.
+const MARKET_MAKER_LIMITS: BetLimits = BetLimits {
+  vote_price: 1_000_000,
+  min_bet: 10_000_000,
+  max_bet: 100_000_000,
+};
+
+/// Started test validator with a market of `pricing` and `limits`, and a funded trader.
+struct LimitedMarket {
+  banks_client: BanksClient,
+  recent_blockhash: Hash,
+  payer: Keypair,
+  trader_kp: Keypair,
+}
+
+async fn setup_limited_market(pricing: MarketPricing, limits: BetLimits) -> LimitedMarket {
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey(MARKET_ID),
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    pricing,
+    MarketParams {
+      limits: Some(limits),
+      ..Default::default()
+    },
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let trader_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    10 * LAMPORTS_PER_SOL,
+  )
+  .await;
+  LimitedMarket {
+    banks_client,
+    recent_blockhash,
+    payer,
+    trader_kp,
+  }
+}
+
+/// Runs each instruction signed by `trader_kp`, expecting `BetOutOfBounds` unless it is marked ok.
+async fn assert_limits(
+  market: &mut LimitedMarket,
+  instructions: impl IntoIterator<Item = (Instruction, bool)>,
+) {
+  for (instruction, is_ok) in instructions {
+    let transaction_result = process(
+      &mut market.banks_client,
+      &mut market.recent_blockhash,
+      &[instruction],
+      &[&market.trader_kp],
+    )
+    .await;
+    if is_ok {
+      assert!(transaction_result.is_ok());
+    } else {
+      assert_eq!(
+        transaction_result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+          0,
+          InstructionError::Custom(HitMyBetError::BetOutOfBounds.into())
+        )
+      );
+    }
+  }
+}
+
+async fn create_share_accounts(market: &mut LimitedMarket) -> [UserShareAccounts; 2] {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut share_accounts = Vec::new();
+  for side in [0, 1] {
+    let share_mint = share_mint_pubkey(&market_pubkey, side);
+    let token_account = create_share_account(
+      &mut market.banks_client,
+      &mut market.recent_blockhash,
+      &market.payer,
+      &share_mint,
+      &market.trader_kp.pubkey(),
+    )
+    .await;
+    share_accounts.push(UserShareAccounts {
+      share_mint,
+      token_account,
+    });
+  }
+  share_accounts.try_into().unwrap()
+}
+
+#[tokio::test]
+async fn test_buy_shares_limits() {
+  let mut market =
+    setup_limited_market(MarketPricing::Lmsr { liquidity: 100 }, MARKET_MAKER_LIMITS).await;
+  let [yes_shares, _] = create_share_accounts(&mut market).await;
+
+  // Around half a vote price per share at even odds.
+  let buy = |num_shares| {
+    buy_shares(
+      hit_my_bet::ID,
+      market.trader_kp.pubkey(),
+      market_pubkey(MARKET_ID),
+      UserVote::Yes,
+      num_shares,
+      u64::MAX,
+      yes_shares,
+      None,
+    )
+  };
+  let instructions = [(buy(10), false), (buy(500), false), (buy(30), true)];
+  assert_limits(&mut market, instructions).await;
+}
+
+#[tokio::test]
+async fn test_mint_complete_set_limits() {
+  let mut market =
+    setup_limited_market(MarketPricing::Lmsr { liquidity: 100 }, MARKET_MAKER_LIMITS).await;
+  let [yes_shares, no_shares] = create_share_accounts(&mut market).await;
+
+  let mint = |num_sets| {
+    mint_complete_set(
+      hit_my_bet::ID,
+      market.trader_kp.pubkey(),
+      market_pubkey(MARKET_ID),
+      CompleteSetAccounts {
+        yes_token_account: yes_shares.token_account,
+        no_token_account: no_shares.token_account,
+      },
+      num_sets,
+      None,
+    )
+  };
+  let instructions = [(mint(9), false), (mint(101), false), (mint(10), true)];
+  assert_limits(&mut market, instructions).await;
+}
+
+#[tokio::test]
+async fn test_place_order_limits() {
+  let mut market = setup_limited_market(MarketPricing::Parimutuel, LIMITS).await;
+
+  let bid = |price, quantity| {
+    place_order(
+      hit_my_bet::ID,
+      market.trader_kp.pubkey(),
+      market_pubkey(MARKET_ID),
+      OrderSide::Bid,
+      price,
+      quantity,
+      None,
+      None,
+    )
+  };
+  let instructions = [
+    (bid(1, LIMITS.min_bet - 1), false),
+    (bid(2, LIMITS.max_bet / 2 + 1), false),
+    (bid(2, LIMITS.min_bet), true),
+  ];
+  assert_limits(&mut market, instructions).await;
+}
@@ -0,0 +1,222 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+  warp_clock,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  claim_market, close_market, init_prediction_market, make_prediction, resolve_market,
+  sweep_unclaimed, MarketParams,
+};
+use hit_my_bet::state::{
+  MarketKind, MarketPricing, MarketResolution, PredictionMarket, Treasury, UserVote,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::rent::Rent;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+const CLAIM_PERIOD: i64 = 3_600;
+
+#[tokio::test]
+async fn test_sweep_unclaimed() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let (treasury_pubkey, _) = Treasury::find_address(&hit_my_bet::ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let mut context = test.start_with_context().await;
+  let payer = context.payer.insecure_clone();
+  let mut recent_blockhash = context.last_blockhash;
+  setup_program_config(&context.banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&context.banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams {
+      claim_period: Some(CLAIM_PERIOD),
+      ..Default::default()
+    },
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let mut voters = Vec::new();
+  for vote in [UserVote::Yes, UserVote::No] {
+    let voter_kp = funded_keypair(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &payer,
+      10 * LAMPORTS_PER_SOL,
+    )
+    .await;
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      vote,
+      3,
+      None,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    voters.push(voter_kp);
+  }
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+  assert_eq!(market.claim_deadline, market.open_until + CLAIM_PERIOD);
+
+  let instruction = sweep_unclaimed(hit_my_bet::ID, payer.pubkey(), market_pubkey, None);
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::ClaimDeadlineNotReached.into())
+    )
+  );
+
+  warp_clock(&mut context, CLAIM_PERIOD).await;
+
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    voters[0].pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voters[0].pubkey()),
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voters[0]],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::ClaimDeadlinePassed.into())
+    )
+  );
+
+  // The sweep takes the unclaimed pools and leaves the market its rent.
+  let market_rent = Rent::default().minimum_balance(PredictionMarket::LEN);
+  let market_balance = context
+    .banks_client
+    .get_balance(market_pubkey)
+    .await
+    .unwrap();
+  assert!(market_balance - market_rent >= 6 * VOTE_PRICE);
+  let treasury_balance = context
+    .banks_client
+    .get_balance(treasury_pubkey)
+    .await
+    .unwrap();
+  let instruction = sweep_unclaimed(hit_my_bet::ID, voters[1].pubkey(), market_pubkey, None);
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voters[1]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    context
+      .banks_client
+      .get_balance(market_pubkey)
+      .await
+      .unwrap(),
+    market_rent
+  );
+  assert_eq!(
+    context
+      .banks_client
+      .get_balance(treasury_pubkey)
+      .await
+      .unwrap(),
+    treasury_balance + market_balance - market_rent
+  );
+
+  // Swept markets can be closed although the winner never claimed.
+  let instruction = close_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    MarketKind::Binary,
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert!(context
+    .banks_client
+    .get_account(market_pubkey)
+    .await
+    .unwrap()
+    .is_none());
+}
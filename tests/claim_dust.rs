@@ -0,0 +1,145 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::instruction::{
+  claim_market, init_prediction_market, make_prediction, resolve_market, MarketParams,
+};
+use hit_my_bet::state::{
+  BetLimits, MarketKind, MarketPricing, MarketResolution, PredictionMarket, UserVote,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+const LIMITS: BetLimits = BetLimits {
+  vote_price: 1,
+  min_bet: 0,
+  max_bet: 0,
+};
+
+#[tokio::test]
+async fn test_last_claimant_takes_dust() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams {
+      limits: Some(LIMITS),
+      ..Default::default()
+    },
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // Three single yes votes split a losing pool of 2, which does not divide by 3.
+  let mut voters = Vec::new();
+  for (vote, num_votes) in [
+    (UserVote::Yes, 1),
+    (UserVote::Yes, 1),
+    (UserVote::Yes, 1),
+    (UserVote::No, 2),
+  ] {
+    let voter_kp = funded_keypair(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      LAMPORTS_PER_SOL,
+    )
+    .await;
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      vote,
+      num_votes,
+      None,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    voters.push(voter_kp);
+  }
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // 1 + floor(2 / 3) each, then the last winner also takes the 2 left over.
+  for (voter_kp, payout) in voters.iter().zip([1, 1, 3, 0]) {
+    let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+    let instruction = claim_market(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+    assert_eq!(balance_before - balance_after, payout);
+  }
+
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.total_claimed, market.balance_yes + market.balance_no);
+  assert_eq!(market.claimed_votes, market.num_yes);
+}
@@ -0,0 +1,631 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::token::{
+  create_mint, create_token_2022_account, create_token_account, create_transfer_fee_mint,
+  get_token_balance, share_mint_pubkey, vault_pubkey,
+};
+use helpers::{funded_keypair, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  cancel_order, claim_market, close_market, init_prediction_market, make_prediction, place_order,
+  resolve_market, submit_resolution_vote, CollateralAccounts, MarketParams, UserCollateralAccounts,
+};
+use hit_my_bet::state::{
+  MarketKind, MarketPricing, MarketResolution, OrderBook, OrderSide, ResolverSet,
+  ResolverSetParams, UserVote,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[tokio::test]
+async fn test_close_market() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let (resolver_set_pubkey, _) = ResolverSet::find_address(&hit_my_bet::ID, &market_pubkey);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let creator_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    10 * LAMPORTS_PER_SOL,
+  )
+  .await;
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    creator_kp.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams {
+      resolver_set: Some(ResolverSetParams {
+        resolvers: vec![resolver_kp.pubkey()],
+        threshold: 1,
+      }),
+      ..Default::default()
+    },
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&creator_kp, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let mut voters = Vec::new();
+  for vote in [UserVote::Yes, UserVote::No] {
+    let voter_kp = funded_keypair(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      10 * LAMPORTS_PER_SOL,
+    )
+    .await;
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      vote,
+      3,
+      None,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    voters.push(voter_kp);
+  }
+
+  let instruction = close_market(
+    hit_my_bet::ID,
+    creator_kp.pubkey(),
+    market_pubkey,
+    MarketKind::Binary,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&creator_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::MarketIsNotResolved.into())
+    )
+  );
+
+  let instruction = submit_resolution_vote(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  for (signer, error) in [
+    (&voters[0], HitMyBetError::InvalidCreator),
+    (&creator_kp, HitMyBetError::ClaimsOutstanding),
+  ] {
+    let instruction = close_market(
+      hit_my_bet::ID,
+      signer.pubkey(),
+      market_pubkey,
+      MarketKind::Binary,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[signer],
+    )
+    .await;
+    assert_eq!(
+      transaction_result.unwrap_err().unwrap(),
+      TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+    );
+  }
+
+  // Only the winner has to claim; the losing prediction can stay open.
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    voters[0].pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voters[0].pubkey()),
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voters[0]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let market_balance = banks_client.get_balance(market_pubkey).await.unwrap();
+  let resolver_set_balance = banks_client.get_balance(resolver_set_pubkey).await.unwrap();
+  let creator_balance = banks_client.get_balance(creator_kp.pubkey()).await.unwrap();
+  let instruction = close_market(
+    hit_my_bet::ID,
+    creator_kp.pubkey(),
+    market_pubkey,
+    MarketKind::Binary,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&creator_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // The resolver set is closed with the market, and no shares can be minted anymore.
+  for closed in [market_pubkey, resolver_set_pubkey] {
+    assert!(banks_client.get_account(closed).await.unwrap().is_none());
+  }
+  assert_share_mints_revoked(&banks_client, &market_pubkey).await;
+  // The creator also paid the transaction fee.
+  let fee = 5_000;
+  assert_eq!(
+    banks_client.get_balance(creator_kp.pubkey()).await.unwrap(),
+    creator_balance + market_balance + resolver_set_balance - fee
+  );
+}
+
+#[tokio::test]
+async fn test_close_market_with_open_order() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let (order_book_pubkey, _) = OrderBook::find_address(&hit_my_bet::ID, &market_pubkey);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let creator_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    10 * LAMPORTS_PER_SOL,
+  )
+  .await;
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    creator_kp.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&creator_kp, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let mut keypairs = Vec::new();
+  for _ in 0..2 {
+    keypairs.push(
+      funded_keypair(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &payer,
+        10 * LAMPORTS_PER_SOL,
+      )
+      .await,
+    );
+  }
+  let [voter_kp, bidder_kp] = &keypairs[..] else {
+    unreachable!()
+  };
+  let instruction = make_prediction(
+    hit_my_bet::ID,
+    voter_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+    UserVote::Yes,
+    3,
+    None,
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[voter_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // A bid the bidder never cancels.
+  let bid_price = VOTE_PRICE / 2;
+  let instruction = place_order(
+    hit_my_bet::ID,
+    bidder_kp.pubkey(),
+    market_pubkey,
+    OrderSide::Bid,
+    bid_price,
+    2,
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[bidder_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // Until the resolution is final only the bidder can cancel it.
+  let cancel_bid = cancel_order(
+    hit_my_bet::ID,
+    creator_kp.pubkey(),
+    bidder_kp.pubkey(),
+    market_pubkey,
+    0,
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[cancel_bid.clone()],
+    &[&creator_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::MarketIsNotResolved.into())
+    )
+  );
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let instruction_claim = claim_market(
+    hit_my_bet::ID,
+    voter_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction, instruction_claim],
+    &[&payer, &resolver_kp, voter_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let close = close_market(
+    hit_my_bet::ID,
+    creator_kp.pubkey(),
+    market_pubkey,
+    MarketKind::Binary,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[close.clone()],
+    &[&creator_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::ClaimsOutstanding.into())
+    )
+  );
+
+  // The creator cancels the leftover bid, whose escrow goes back to the bidder.
+  let bidder_balance = banks_client.get_balance(bidder_kp.pubkey()).await.unwrap();
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[cancel_bid, close],
+    &[&creator_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    banks_client.get_balance(bidder_kp.pubkey()).await.unwrap() - bidder_balance,
+    2 * bid_price
+  );
+  for closed in [market_pubkey, order_book_pubkey] {
+    assert!(banks_client.get_account(closed).await.unwrap().is_none());
+  }
+}
+
+#[tokio::test]
+async fn test_close_token_market() {
+  close_token_market(false).await;
+}
+
+#[tokio::test]
+async fn test_close_transfer_fee_market() {
+  close_token_market(true).await;
+}
+
+/// Runs a token market to the end and closes it, with an SPL Token mint or a Token-2022 mint
+/// charging transfer fees.
+async fn close_token_market(transfer_fee: bool) {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let vault_pubkey = vault_pubkey(&market_pubkey);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+  let (mint, token_program) = if transfer_fee {
+    let mint = create_transfer_fee_mint(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      100,
+      u64::MAX,
+    )
+    .await;
+    (mint, spl_token_2022::ID)
+  } else {
+    let mint = create_mint(&mut banks_client, &mut recent_blockhash, &payer).await;
+    (mint, spl_token::ID)
+  };
+
+  let creator_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    10 * LAMPORTS_PER_SOL,
+  )
+  .await;
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    creator_kp.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    Some(CollateralAccounts {
+      mint,
+      vault: vault_pubkey,
+      token_program,
+      funding_account: None,
+    }),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&creator_kp, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let mut token_accounts = Vec::new();
+  for owner in [None, Some(UserVote::Yes), Some(UserVote::No)] {
+    let owner_kp = match owner {
+      None => creator_kp.insecure_clone(),
+      Some(_) => {
+        funded_keypair(
+          &mut banks_client,
+          &mut recent_blockhash,
+          &payer,
+          LAMPORTS_PER_SOL,
+        )
+        .await
+      }
+    };
+    let amount = if owner.is_some() { 10 * VOTE_PRICE } else { 0 };
+    let token_account = if transfer_fee {
+      create_token_2022_account(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &payer,
+        &mint,
+        &owner_kp.pubkey(),
+        amount,
+      )
+      .await
+    } else {
+      create_token_account(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &payer,
+        &mint,
+        &owner_kp.pubkey(),
+        amount,
+      )
+      .await
+    };
+    let collateral = UserCollateralAccounts {
+      token_account,
+      mint,
+      vault: vault_pubkey,
+      token_program,
+    };
+    if let Some(vote) = owner {
+      let instruction = make_prediction(
+        hit_my_bet::ID,
+        owner_kp.pubkey(),
+        market_pubkey,
+        user_prediction_pubkey(&market_pubkey, &owner_kp.pubkey()),
+        vote,
+        2,
+        Some(collateral),
+        None,
+        None,
+      );
+      let transaction_result = process(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &[instruction],
+        &[&owner_kp],
+      )
+      .await;
+      assert!(transaction_result.is_ok());
+    }
+    token_accounts.push((owner_kp, collateral));
+  }
+  let [(_, creator_collateral), (yes_kp, yes_collateral), _] = &token_accounts[..] else {
+    unreachable!()
+  };
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let instruction_claim = claim_market(
+    hit_my_bet::ID,
+    yes_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &yes_kp.pubkey()),
+    Some(*yes_collateral),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction, instruction_claim],
+    &[&payer, &resolver_kp, yes_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(get_token_balance(&banks_client, vault_pubkey).await, 0);
+
+  let instruction = close_market(
+    hit_my_bet::ID,
+    creator_kp.pubkey(),
+    market_pubkey,
+    MarketKind::Binary,
+    Some(*creator_collateral),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&creator_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  for closed in [market_pubkey, vault_pubkey] {
+    assert!(banks_client.get_account(closed).await.unwrap().is_none());
+  }
+  if transfer_fee {
+    // The fees withheld on the bets went to the mint with the vault closed.
+    assert!(withheld_in_mint(&banks_client, mint).await > 0);
+  }
+}
+
+async fn withheld_in_mint(banks_client: &BanksClient, mint: Pubkey) -> u64 {
+  let mint_account = banks_client.get_account(mint).await.unwrap().unwrap();
+  let mint_state =
+    StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_account.data).unwrap();
+  u64::from(
+    mint_state
+      .get_extension::<TransferFeeConfig>()
+      .unwrap()
+      .withheld_amount,
+  )
+}
+
+async fn assert_share_mints_revoked(banks_client: &BanksClient, market_pubkey: &Pubkey) {
+  for side in [0, 1] {
+    let share_mint = banks_client
+      .get_account(share_mint_pubkey(market_pubkey, side))
+      .await
+      .unwrap()
+      .unwrap();
+    let share_mint = spl_token::state::Mint::unpack(&share_mint.data).unwrap();
+    assert_eq!(share_mint.mint_authority, COption::None);
+  }
+}
@@ -0,0 +1,258 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::token::{create_share_account, get_token_balance, share_mint_pubkey};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  claim_market, init_prediction_market, mint_complete_set, redeem_complete_set, resolve_market,
+  CompleteSetAccounts, MarketParams, UserShareAccounts,
+};
+use hit_my_bet::state::{MarketKind, MarketPricing, MarketResolution, PredictionMarket};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+const PARIMUTUEL_MARKET_ID: &[u8; 16] = b"parimutuel_mkt16";
+
+#[tokio::test]
+async fn test_complete_set() {
+  let parimutuel_pubkey = market_pubkey(PARIMUTUEL_MARKET_ID);
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  for (market_pubkey, market_id, pricing) in [
+    (
+      market_pubkey,
+      MARKET_ID,
+      MarketPricing::Lmsr { liquidity: 100 },
+    ),
+    (
+      parimutuel_pubkey,
+      PARIMUTUEL_MARKET_ID,
+      MarketPricing::Parimutuel,
+    ),
+  ] {
+    let instruction = init_prediction_market(
+      hit_my_bet::ID,
+      payer.pubkey(),
+      market_pubkey,
+      resolver_kp.pubkey(),
+      config_pubkey(),
+      *market_id,
+      timestamp + 60,
+      MarketKind::Binary,
+      pricing,
+      MarketParams::default(),
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&payer, &resolver_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+  }
+
+  let user_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  let mut share_accounts = Vec::new();
+  for side in [0, 1] {
+    share_accounts.push(
+      create_share_account(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &payer,
+        &share_mint_pubkey(&market_pubkey, side),
+        &user_kp.pubkey(),
+      )
+      .await,
+    );
+  }
+  let shares = CompleteSetAccounts {
+    yes_token_account: share_accounts[0],
+    no_token_account: share_accounts[1],
+  };
+
+  // Parimutuel shares pay a share of the pools, which a set cannot be backed by.
+  let mut parimutuel_accounts = Vec::new();
+  for side in [0, 1] {
+    parimutuel_accounts.push(
+      create_share_account(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &payer,
+        &share_mint_pubkey(&parimutuel_pubkey, side),
+        &user_kp.pubkey(),
+      )
+      .await,
+    );
+  }
+  let instruction = mint_complete_set(
+    hit_my_bet::ID,
+    user_kp.pubkey(),
+    parimutuel_pubkey,
+    CompleteSetAccounts {
+      yes_token_account: parimutuel_accounts[0],
+      no_token_account: parimutuel_accounts[1],
+    },
+    3,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&user_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidPricingMode.into())
+    )
+  );
+
+  let market_lamports = banks_client.get_balance(market_pubkey).await.unwrap();
+  let instruction = mint_complete_set(
+    hit_my_bet::ID,
+    user_kp.pubkey(),
+    market_pubkey,
+    shares,
+    3,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&user_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  for share_account in &share_accounts {
+    assert_eq!(get_token_balance(&banks_client, *share_account).await, 3);
+  }
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!((market.num_yes, market.num_no), (3, 3));
+  assert_eq!(
+    banks_client.get_balance(market_pubkey).await.unwrap(),
+    market_lamports + 3 * VOTE_PRICE
+  );
+
+  let instruction = redeem_complete_set(
+    hit_my_bet::ID,
+    user_kp.pubkey(),
+    market_pubkey,
+    shares,
+    2,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&user_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  for share_account in &share_accounts {
+    assert_eq!(get_token_balance(&banks_client, *share_account).await, 1);
+  }
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!((market.num_yes, market.num_no), (1, 1));
+  assert_eq!(
+    banks_client.get_balance(market_pubkey).await.unwrap(),
+    market_lamports + VOTE_PRICE
+  );
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let instruction = redeem_complete_set(
+    hit_my_bet::ID,
+    user_kp.pubkey(),
+    market_pubkey,
+    shares,
+    1,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&user_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::MarketIsResolved.into())
+    )
+  );
+
+  // The winning share of the remaining set pays back the collateral it was minted with.
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    user_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &user_kp.pubkey()),
+    None,
+    Some(UserShareAccounts {
+      share_mint: share_mint_pubkey(&market_pubkey, 0),
+      token_account: share_accounts[0],
+    }),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&user_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    banks_client.get_balance(market_pubkey).await.unwrap(),
+    market_lamports
+  );
+}
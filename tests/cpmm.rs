@@ -0,0 +1,385 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::token::{create_share_account, get_token_balance, share_mint_pubkey};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::cpmm;
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  add_liquidity, buy_shares, claim_market, init_prediction_market, remove_liquidity,
+  resolve_market, sell_shares, MarketParams, UserShareAccounts,
+};
+use hit_my_bet::state::{
+  LiquidityPosition, MarketKind, MarketPricing, MarketResolution, PredictionMarket, UserVote,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[test]
+fn test_constant_product() {
+  // (1000 + c - 100) * (1000 + c) >= 1000 * 1000 first holds for c = 52.
+  assert_eq!(cpmm::buy((1000, 1000), true, 100), Some((52, (952, 1052))));
+  assert_eq!(cpmm::buy((1000, 1000), false, 100), Some((52, (1052, 952))));
+  // (1052 - p)^2 >= 952 * 1052 last holds for p = 51.
+  assert_eq!(cpmm::sell((952, 1052), true, 100), Some((51, (1001, 1001))));
+
+  let (cost, reserves) = cpmm::buy((3_000, 500), false, 700).unwrap();
+  assert!(u128::from(reserves.0) * u128::from(reserves.1) >= 3_000 * 500);
+  let (proceeds, reserves) = cpmm::sell(reserves, false, 700).unwrap();
+  assert!(proceeds <= cost);
+  assert!(u128::from(reserves.0) * u128::from(reserves.1) >= 3_000 * 500);
+
+  assert_eq!(cpmm::buy((0, 1000), true, 100), None);
+}
+
+#[tokio::test]
+async fn test_cpmm_market() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let yes_mint = share_mint_pubkey(&market_pubkey, 0);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Cpmm,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market_lamports = banks_client.get_balance(market_pubkey).await.unwrap();
+
+  let mut keypairs = Vec::new();
+  for _ in 0..3 {
+    keypairs.push(
+      funded_keypair(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &payer,
+        20 * LAMPORTS_PER_SOL,
+      )
+      .await,
+    );
+  }
+  let [trader_kp, first_lp_kp, second_lp_kp] = &keypairs[..] else {
+    unreachable!()
+  };
+  let share_account = create_share_account(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    &yes_mint,
+    &trader_kp.pubkey(),
+  )
+  .await;
+  let shares = UserShareAccounts {
+    share_mint: yes_mint,
+    token_account: share_account,
+  };
+
+  let instruction = buy_shares(
+    hit_my_bet::ID,
+    trader_kp.pubkey(),
+    market_pubkey,
+    UserVote::Yes,
+    10,
+    u64::MAX,
+    shares,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[trader_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InsufficientLiquidity.into())
+    )
+  );
+
+  let instruction = add_liquidity(
+    hit_my_bet::ID,
+    first_lp_kp.pubkey(),
+    market_pubkey,
+    100 * VOTE_PRICE,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[first_lp_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(
+    (market.reserve_yes, market.reserve_no, market.lp_supply),
+    (100 * VOTE_PRICE, 100 * VOTE_PRICE, 100 * VOTE_PRICE)
+  );
+
+  let (cost, _) = cpmm::buy(
+    (market.reserve_yes, market.reserve_no),
+    true,
+    10 * VOTE_PRICE,
+  )
+  .unwrap();
+  for (max_cost, is_ok) in [(cost - 1, false), (cost, true)] {
+    let instruction = buy_shares(
+      hit_my_bet::ID,
+      trader_kp.pubkey(),
+      market_pubkey,
+      UserVote::Yes,
+      10,
+      max_cost,
+      shares,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[trader_kp],
+    )
+    .await;
+    if is_ok {
+      assert!(transaction_result.is_ok());
+    } else {
+      assert_eq!(
+        transaction_result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+          0,
+          InstructionError::Custom(HitMyBetError::SlippageExceeded.into())
+        )
+      );
+    }
+  }
+  assert_eq!(get_token_balance(&banks_client, share_account).await, 10);
+
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  let (proceeds, _) = cpmm::sell(
+    (market.reserve_yes, market.reserve_no),
+    true,
+    4 * VOTE_PRICE,
+  )
+  .unwrap();
+  let instruction = sell_shares(
+    hit_my_bet::ID,
+    trader_kp.pubkey(),
+    market_pubkey,
+    UserVote::Yes,
+    4,
+    proceeds,
+    shares,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[trader_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(get_token_balance(&banks_client, share_account).await, 6);
+  assert_eq!(
+    banks_client.get_balance(market_pubkey).await.unwrap(),
+    market_lamports + 100 * VOTE_PRICE + cost - proceeds
+  );
+
+  // The pool now holds more NO than YES, so part of a new deposit is kept as YES exposure.
+  let instruction = add_liquidity(
+    hit_my_bet::ID,
+    second_lp_kp.pubkey(),
+    market_pubkey,
+    50 * VOTE_PRICE,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[second_lp_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let position: LiquidityPosition = get_state(
+    &banks_client,
+    position_pubkey(&market_pubkey, &second_lp_kp.pubkey()),
+  )
+  .await;
+  assert!(position.lp_shares > 0);
+  assert_eq!(position.excess_no, 0);
+  assert!(position.excess_yes > 0);
+
+  let position: LiquidityPosition = get_state(
+    &banks_client,
+    position_pubkey(&market_pubkey, &first_lp_kp.pubkey()),
+  )
+  .await;
+  let instruction = remove_liquidity(
+    hit_my_bet::ID,
+    first_lp_kp.pubkey(),
+    market_pubkey,
+    position.lp_shares + 1,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[first_lp_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(0, InstructionError::InsufficientFunds)
+  );
+
+  let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+  let instruction = remove_liquidity(
+    hit_my_bet::ID,
+    first_lp_kp.pubkey(),
+    market_pubkey,
+    position.lp_shares / 2,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[first_lp_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+  let position: LiquidityPosition = get_state(
+    &banks_client,
+    position_pubkey(&market_pubkey, &first_lp_kp.pubkey()),
+  )
+  .await;
+  assert!(balance_before > balance_after);
+  assert_eq!(position.excess_yes, 0);
+  assert!(position.excess_no > 0);
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    trader_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &trader_kp.pubkey()),
+    None,
+    Some(shares),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[trader_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+  assert_eq!(balance_before - balance_after, 6 * VOTE_PRICE);
+
+  // Liquidity providers are paid their YES reserves and exposure, which leaves the market with
+  // its rent and rounding dust.
+  for lp_kp in [first_lp_kp, second_lp_kp] {
+    let position_pubkey = position_pubkey(&market_pubkey, &lp_kp.pubkey());
+    let position: LiquidityPosition = get_state(&banks_client, position_pubkey).await;
+    let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+    let expected = u64::try_from(
+      u128::from(market.reserve_yes) * u128::from(position.lp_shares)
+        / u128::from(market.lp_supply),
+    )
+    .unwrap()
+      + position.excess_yes;
+
+    let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+    let instruction = claim_market(
+      hit_my_bet::ID,
+      lp_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &lp_kp.pubkey()),
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[lp_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+    assert_eq!(balance_before - balance_after, expected);
+    assert!(banks_client
+      .get_account(position_pubkey)
+      .await
+      .unwrap()
+      .is_none());
+  }
+
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.lp_supply, 0);
+  assert!(banks_client.get_balance(market_pubkey).await.unwrap() - market_lamports < 10);
+}
+
+fn position_pubkey(market_pubkey: &Pubkey, provider: &Pubkey) -> Pubkey {
+  let (position_pubkey, _) =
+    LiquidityPosition::find_address(&hit_my_bet::ID, market_pubkey, provider);
+  position_pubkey
+}
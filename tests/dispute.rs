@@ -0,0 +1,412 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+  warp_clock,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  claim_market, dispute_resolution, init_prediction_market, make_prediction, resolve_market,
+  resolve_market_admin, MarketParams,
+};
+use hit_my_bet::state::{
+  MarketKind, MarketPricing, MarketResolution, PredictionMarket, ResolutionStatus, UserVote,
+};
+use solana_program::clock::UnixTimestamp;
+use solana_program::hash::Hash;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+const DISPUTE_PERIOD: i64 = 3_600;
+
+/// Binary market with a yes and a no voter of 3 votes each, resolved yes by its resolver.
+struct DisputedMarket {
+  context: ProgramTestContext,
+  recent_blockhash: Hash,
+  payer: Keypair,
+  admin_kp: Keypair,
+  resolver_kp: Keypair,
+  voters: Vec<Keypair>,
+}
+
+async fn setup_resolved_market(dispute_period: Option<UnixTimestamp>) -> DisputedMarket {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let mut context = test.start_with_context().await;
+  let payer = context.payer.insecure_clone();
+  let mut recent_blockhash = context.last_blockhash;
+  let admin_kp = setup_program_config(&context.banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&context.banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams {
+      dispute_period,
+      ..Default::default()
+    },
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let mut voters = Vec::new();
+  for vote in [UserVote::Yes, UserVote::No] {
+    let voter_kp = funded_keypair(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &payer,
+      10 * LAMPORTS_PER_SOL,
+    )
+    .await;
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      vote,
+      3,
+      None,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    voters.push(voter_kp);
+  }
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  DisputedMarket {
+    context,
+    recent_blockhash,
+    payer,
+    admin_kp,
+    resolver_kp,
+    voters,
+  }
+}
+
+fn claim(market_pubkey: Pubkey, voter: &Keypair) -> solana_program::instruction::Instruction {
+  claim_market(
+    hit_my_bet::ID,
+    voter.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voter.pubkey()),
+    None,
+    None,
+  )
+}
+
+fn custom_error(error: HitMyBetError) -> TransactionError {
+  TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+}
+
+#[tokio::test]
+async fn test_invalid_dispute_period() {
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey(MARKET_ID),
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams {
+      dispute_period: Some(0),
+      ..Default::default()
+    },
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::InvalidDisputePeriod)
+  );
+}
+
+#[tokio::test]
+async fn test_claims_wait_for_dispute_period() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let DisputedMarket {
+    mut context,
+    mut recent_blockhash,
+    payer,
+    voters,
+    ..
+  } = setup_resolved_market(Some(DISPUTE_PERIOD)).await;
+
+  let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+  assert_eq!(market.resolution, MarketResolution::Yes);
+  assert_eq!(market.resolution_status, ResolutionStatus::Proposed);
+  assert_eq!(market.dispute_deadline, market.open_until + DISPUTE_PERIOD);
+
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[claim(market_pubkey, &voters[0])],
+    &[&voters[0]],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::ResolutionNotFinal)
+  );
+
+  // The proposed resolution becomes final once the dispute period ends.
+  warp_clock(&mut context, DISPUTE_PERIOD).await;
+
+  let instruction = dispute_resolution(hit_my_bet::ID, payer.pubkey(), market_pubkey, None);
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::ResolutionNotDisputable)
+  );
+
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[claim(market_pubkey, &voters[0])],
+    &[&voters[0]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+}
+
+#[tokio::test]
+async fn test_admin_settles_dispute() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let DisputedMarket {
+    mut context,
+    mut recent_blockhash,
+    payer,
+    admin_kp,
+    resolver_kp,
+    voters,
+  } = setup_resolved_market(Some(DISPUTE_PERIOD)).await;
+
+  // Without a resolution bond only market participants can dispute.
+  let instruction = dispute_resolution(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    Some(user_prediction_pubkey(&market_pubkey, &payer.pubkey())),
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::NotMarketParticipant)
+  );
+
+  let instruction = dispute_resolution(
+    hit_my_bet::ID,
+    voters[1].pubkey(),
+    market_pubkey,
+    Some(user_prediction_pubkey(&market_pubkey, &voters[1].pubkey())),
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voters[1]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+  assert_eq!(market.resolution_status, ResolutionStatus::UnderDispute);
+
+  // The resolver cannot resolve again, and the dispute outlives the dispute period.
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::No,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::MarketIsResolved)
+  );
+
+  warp_clock(&mut context, DISPUTE_PERIOD).await;
+
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[claim(market_pubkey, &voters[0])],
+    &[&voters[0]],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::ResolutionNotFinal)
+  );
+
+  let instruction = resolve_market_admin(
+    hit_my_bet::ID,
+    admin_kp.pubkey(),
+    market_pubkey,
+    config_pubkey(),
+    MarketResolution::No,
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+  assert_eq!(market.resolution, MarketResolution::No);
+  assert_eq!(market.resolution_status, ResolutionStatus::Final);
+
+  let balance_before = context
+    .banks_client
+    .get_balance(voters[1].pubkey())
+    .await
+    .unwrap();
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[claim(market_pubkey, &voters[1])],
+    &[&voters[1]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert!(
+    context
+      .banks_client
+      .get_balance(voters[1].pubkey())
+      .await
+      .unwrap()
+      > balance_before
+  );
+}
+
+#[tokio::test]
+async fn test_resolution_without_dispute_period_is_final() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let DisputedMarket {
+    mut context,
+    mut recent_blockhash,
+    voters,
+    ..
+  } = setup_resolved_market(None).await;
+
+  let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+  assert_eq!(market.resolution_status, ResolutionStatus::Final);
+
+  let instruction = dispute_resolution(
+    hit_my_bet::ID,
+    voters[1].pubkey(),
+    market_pubkey,
+    Some(user_prediction_pubkey(&market_pubkey, &voters[1].pubkey())),
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voters[1]],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::ResolutionNotDisputable)
+  );
+
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[claim(market_pubkey, &voters[0])],
+    &[&voters[0]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+}
@@ -0,0 +1,369 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  claim_market, init_prediction_market, make_prediction, make_scalar_prediction, resolve_market,
+  withdraw_creator_fees, MarketParams,
+};
+use hit_my_bet::state::{
+  MarketFees, MarketKind, MarketPricing, MarketResolution, PredictionMarket, ScalarPosition,
+  Treasury, UserVote,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[tokio::test]
+async fn test_fees() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let (treasury_pubkey, _) = Treasury::find_address(&hit_my_bet::ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let init_market = |fees| {
+    init_prediction_market(
+      hit_my_bet::ID,
+      payer.pubkey(),
+      market_pubkey,
+      resolver_kp.pubkey(),
+      config_pubkey(),
+      *MARKET_ID,
+      timestamp + 60,
+      MarketKind::Binary,
+      MarketPricing::Parimutuel,
+      MarketParams {
+        fees: Some(fees),
+        ..Default::default()
+      },
+      None,
+    )
+  };
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[init_market(MarketFees {
+      protocol_fee_bps: 600,
+      creator_fee_bps: 401,
+      referral_fee_bps: 0,
+    })],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidFees.into())
+    )
+  );
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[init_market(MarketFees {
+      protocol_fee_bps: 100,
+      creator_fee_bps: 200,
+      referral_fee_bps: 0,
+    })],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let mut voters = vec![];
+  for vote in [UserVote::Yes, UserVote::No] {
+    let voter_kp = funded_keypair(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      10 * LAMPORTS_PER_SOL,
+    )
+    .await;
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      vote,
+      3,
+      None,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    voters.push(voter_kp);
+  }
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // The winner takes back 3 votes and wins 3; fees are only charged on the 3 won.
+  let winnings = 3 * VOTE_PRICE;
+  let protocol_fee = winnings / 100;
+  let creator_fee = winnings * 2 / 100;
+
+  let market_before = banks_client.get_balance(market_pubkey).await.unwrap();
+  let treasury_before = banks_client.get_balance(treasury_pubkey).await.unwrap();
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    voters[0].pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voters[0].pubkey()),
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voters[0]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // The creator's cut stays in the market until it is withdrawn.
+  let market_after = banks_client.get_balance(market_pubkey).await.unwrap();
+  assert_eq!(market_before - market_after, 6 * VOTE_PRICE - creator_fee);
+  assert_eq!(
+    banks_client.get_balance(treasury_pubkey).await.unwrap() - treasury_before,
+    protocol_fee
+  );
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.creator_fees, creator_fee);
+  assert_eq!(market.total_claimed, 6 * VOTE_PRICE);
+
+  let instruction = withdraw_creator_fees(hit_my_bet::ID, voters[1].pubkey(), market_pubkey, None);
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voters[1]],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidCreator.into())
+    )
+  );
+
+  let instruction = withdraw_creator_fees(hit_my_bet::ID, payer.pubkey(), market_pubkey, None);
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    market_after - banks_client.get_balance(market_pubkey).await.unwrap(),
+    creator_fee
+  );
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.creator_fees, 0);
+}
+
+#[tokio::test]
+async fn test_scalar_fees() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let (treasury_pubkey, _) = Treasury::find_address(&hit_my_bet::ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let init_market = |kind, pricing| {
+    init_prediction_market(
+      hit_my_bet::ID,
+      payer.pubkey(),
+      market_pubkey,
+      resolver_kp.pubkey(),
+      config_pubkey(),
+      *MARKET_ID,
+      timestamp + 60,
+      kind,
+      pricing,
+      MarketParams {
+        fees: Some(MarketFees {
+          protocol_fee_bps: 100,
+          creator_fee_bps: 200,
+          referral_fee_bps: 0,
+        }),
+        ..Default::default()
+      },
+      None,
+    )
+  };
+
+  // Market maker shares have no stake to tell winnings from.
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[init_market(MarketKind::Binary, MarketPricing::Cpmm)],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidFees.into())
+    )
+  );
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[init_market(
+      MarketKind::Scalar { min: 0, max: 100 },
+      MarketPricing::Parimutuel,
+    )],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let long_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  let short_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  for (voter, position, num_votes) in [
+    (&long_kp, ScalarPosition::Long, 3),
+    (&short_kp, ScalarPosition::Short, 1),
+  ] {
+    let instruction = make_scalar_prediction(
+      hit_my_bet::ID,
+      voter.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter.pubkey()),
+      position,
+      num_votes,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[voter],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+  }
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Scalar(25),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // The long side staked 3 votes and gets 1 back, so it pays no fee. The short side staked 1
+  // vote and gets 3 back, so it pays fees on the 2 won.
+  let short_winnings = 2 * VOTE_PRICE;
+  for (voter, payout, protocol_fee, creator_fee) in [
+    (&long_kp, VOTE_PRICE, 0, 0),
+    (
+      &short_kp,
+      3 * VOTE_PRICE,
+      short_winnings / 100,
+      short_winnings * 2 / 100,
+    ),
+  ] {
+    let market_before = banks_client.get_balance(market_pubkey).await.unwrap();
+    let treasury_before = banks_client.get_balance(treasury_pubkey).await.unwrap();
+    let instruction = claim_market(
+      hit_my_bet::ID,
+      voter.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter.pubkey()),
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[voter],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+
+    assert_eq!(
+      market_before - banks_client.get_balance(market_pubkey).await.unwrap(),
+      payout - creator_fee
+    );
+    assert_eq!(
+      banks_client.get_balance(treasury_pubkey).await.unwrap() - treasury_before,
+      protocol_fee
+    );
+  }
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.creator_fees, short_winnings * 2 / 100);
+}
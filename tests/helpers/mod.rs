@@ -1 +1,90 @@
+#![allow(dead_code)]
+
+pub mod program_config;
 pub mod random_market;
+pub mod token;
+
+use solana_program::clock::UnixTimestamp;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{
+  BanksClient, BanksClientError, ProgramTestBanksClientExt, ProgramTestContext,
+};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+
+pub async fn process(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  instructions: &[Instruction],
+  signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+  *recent_blockhash = client
+    .get_new_latest_blockhash(recent_blockhash)
+    .await
+    .unwrap();
+  let mut transaction = Transaction::new_with_payer(instructions, Some(&signers[0].pubkey()));
+  transaction.sign(signers, *recent_blockhash);
+  client.process_transaction(transaction).await
+}
+
+pub async fn funded_keypair(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  payer: &Keypair,
+  lamports: u64,
+) -> Keypair {
+  let keypair = Keypair::new();
+  let instruction =
+    solana_program::system_instruction::transfer(&payer.pubkey(), &keypair.pubkey(), lamports);
+  process(client, recent_blockhash, &[instruction], &[payer])
+    .await
+    .expect("fund keypair");
+  keypair
+}
+
+pub async fn get_state<T: borsh::BorshDeserialize>(client: &BanksClient, address: Pubkey) -> T {
+  let account = client.get_account(address).await.unwrap().unwrap();
+  borsh::BorshDeserialize::deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+pub async fn get_unix_timestamp(client: &BanksClient) -> UnixTimestamp {
+  let clock = client
+    .get_account(solana_program::clock::sysvar::ID)
+    .await
+    .unwrap()
+    .unwrap();
+  UnixTimestamp::from_le_bytes(clock.data[32..].as_ref().try_into().unwrap())
+}
+
+/// Moves the clock of `context` forward by `seconds`.
+pub async fn warp_clock(context: &mut ProgramTestContext, seconds: UnixTimestamp) {
+  let mut clock: solana_program::clock::Clock = context.banks_client.get_sysvar().await.unwrap();
+  clock.unix_timestamp += seconds;
+  context.set_sysvar(&clock);
+}
+
+pub fn market_pubkey(market_id: &[u8; 16]) -> Pubkey {
+  let (market_pubkey, _) = Pubkey::find_program_address(
+    &[
+      hit_my_bet::state::PredictionMarket::SEED_PREFIX.as_bytes(),
+      market_id,
+    ],
+    &hit_my_bet::ID,
+  );
+  market_pubkey
+}
+
+pub fn user_prediction_pubkey(market_pubkey: &Pubkey, voter: &Pubkey) -> Pubkey {
+  let (prediction_pubkey, _) = Pubkey::find_program_address(
+    &[
+      hit_my_bet::state::UserPrediction::SEED_PREFIX.as_bytes(),
+      market_pubkey.as_ref(),
+      voter.as_ref(),
+    ],
+    &hit_my_bet::ID,
+  );
+  prediction_pubkey
+}
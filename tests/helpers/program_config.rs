@@ -0,0 +1,66 @@
+use hit_my_bet::instruction::initialize_config;
+use hit_my_bet::state::ProgramConfig;
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_program::hash::Hash;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{BanksClient, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::{SeedDerivable, Signer};
+use solana_sdk::transaction::Transaction;
+
+pub const CREATE_MARKET_FEE: u64 = LAMPORTS_PER_SOL / 10;
+
+pub const VOTE_PRICE: u64 = LAMPORTS_PER_SOL / 10;
+
+pub fn config_pubkey() -> Pubkey {
+  let (config_pubkey, _) =
+    Pubkey::find_program_address(&[ProgramConfig::SEED_PREFIX.as_bytes()], &hit_my_bet::ID);
+  config_pubkey
+}
+
+/// Upgrade authority of the program in tests, and the config admin set up by
+/// `setup_program_config`.
+pub fn upgrade_authority() -> Keypair {
+  Keypair::from_seed(&[7; 32]).expect("upgrade authority")
+}
+
+/// Adds the program data account naming `upgrade_authority` as the program's upgrade authority.
+pub fn add_program_data(test: &mut ProgramTest) {
+  test.add_account(
+    bpf_loader_upgradeable::get_program_data_address(&hit_my_bet::ID),
+    Account::new_data(
+      LAMPORTS_PER_SOL,
+      &UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(upgrade_authority().pubkey()),
+      },
+      &bpf_loader_upgradeable::ID,
+    )
+    .expect("program data"),
+  );
+}
+
+pub async fn setup_program_config(
+  banks_client: &BanksClient,
+  payer: &Keypair,
+  recent_blockhash: Hash,
+) -> Keypair {
+  let admin_kp = upgrade_authority();
+  let instruction = initialize_config(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    config_pubkey(),
+    admin_kp.pubkey(),
+    CREATE_MARKET_FEE,
+    VOTE_PRICE,
+  );
+  let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+  transaction.sign(&[payer, &admin_kp], recent_blockhash);
+  banks_client
+    .process_transaction(transaction)
+    .await
+    .expect("initialize config");
+  admin_kp
+}
@@ -0,0 +1,289 @@
+use crate::helpers::process;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_instruction;
+use solana_program_test::BanksClient;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+
+pub const DECIMALS: u8 = 6;
+
+pub async fn create_mint(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  payer: &Keypair,
+) -> Pubkey {
+  let mint_kp = Keypair::new();
+  let instructions = [
+    system_instruction::create_account(
+      &payer.pubkey(),
+      &mint_kp.pubkey(),
+      Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+      spl_token::state::Mint::LEN as u64,
+      &spl_token::ID,
+    ),
+    spl_token::instruction::initialize_mint2(
+      &spl_token::ID,
+      &mint_kp.pubkey(),
+      &payer.pubkey(),
+      None,
+      DECIMALS,
+    )
+    .unwrap(),
+  ];
+  process(client, recent_blockhash, &instructions, &[payer, &mint_kp])
+    .await
+    .expect("create mint");
+  mint_kp.pubkey()
+}
+
+pub async fn create_token_account(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  payer: &Keypair,
+  mint: &Pubkey,
+  owner: &Pubkey,
+  amount: u64,
+) -> Pubkey {
+  let account_kp = Keypair::new();
+  let instructions = [
+    system_instruction::create_account(
+      &payer.pubkey(),
+      &account_kp.pubkey(),
+      Rent::default().minimum_balance(spl_token::state::Account::LEN),
+      spl_token::state::Account::LEN as u64,
+      &spl_token::ID,
+    ),
+    spl_token::instruction::initialize_account3(&spl_token::ID, &account_kp.pubkey(), mint, owner)
+      .unwrap(),
+    spl_token::instruction::mint_to(
+      &spl_token::ID,
+      mint,
+      &account_kp.pubkey(),
+      &payer.pubkey(),
+      &[],
+      amount,
+    )
+    .unwrap(),
+  ];
+  process(
+    client,
+    recent_blockhash,
+    &instructions,
+    &[payer, &account_kp],
+  )
+  .await
+  .expect("create token account");
+  account_kp.pubkey()
+}
+
+/// Creates a Token-2022 mint with a transfer fee of `fee_basis_points`, capped at `max_fee`.
+pub async fn create_transfer_fee_mint(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  payer: &Keypair,
+  fee_basis_points: u16,
+  max_fee: u64,
+) -> Pubkey {
+  let mint_kp = Keypair::new();
+  let init_extension =
+    spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+      &spl_token_2022::ID,
+      &mint_kp.pubkey(),
+      None,
+      None,
+      fee_basis_points,
+      max_fee,
+    )
+    .unwrap();
+  create_mint_2022(
+    client,
+    recent_blockhash,
+    payer,
+    mint_kp,
+    ExtensionType::TransferFeeConfig,
+    init_extension,
+  )
+  .await
+}
+
+/// Creates a Token-2022 mint whose permanent delegate is the payer.
+pub async fn create_permanent_delegate_mint(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  payer: &Keypair,
+) -> Pubkey {
+  let mint_kp = Keypair::new();
+  let init_extension = spl_token_2022::instruction::initialize_permanent_delegate(
+    &spl_token_2022::ID,
+    &mint_kp.pubkey(),
+    &payer.pubkey(),
+  )
+  .unwrap();
+  create_mint_2022(
+    client,
+    recent_blockhash,
+    payer,
+    mint_kp,
+    ExtensionType::PermanentDelegate,
+    init_extension,
+  )
+  .await
+}
+
+async fn create_mint_2022(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  payer: &Keypair,
+  mint_kp: Keypair,
+  extension: ExtensionType,
+  init_extension: Instruction,
+) -> Pubkey {
+  let mint_len =
+    ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[extension]).unwrap();
+  let instructions = [
+    system_instruction::create_account(
+      &payer.pubkey(),
+      &mint_kp.pubkey(),
+      Rent::default().minimum_balance(mint_len),
+      mint_len as u64,
+      &spl_token_2022::ID,
+    ),
+    init_extension,
+    spl_token_2022::instruction::initialize_mint2(
+      &spl_token_2022::ID,
+      &mint_kp.pubkey(),
+      &payer.pubkey(),
+      None,
+      DECIMALS,
+    )
+    .unwrap(),
+  ];
+  process(client, recent_blockhash, &instructions, &[payer, &mint_kp])
+    .await
+    .expect("create token-2022 mint");
+  mint_kp.pubkey()
+}
+
+pub async fn create_token_2022_account(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  payer: &Keypair,
+  mint: &Pubkey,
+  owner: &Pubkey,
+  amount: u64,
+) -> Pubkey {
+  let mint_account = client.get_account(*mint).await.unwrap().unwrap();
+  let mint_state =
+    StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_account.data).unwrap();
+  let account_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+    &ExtensionType::get_required_init_account_extensions(
+      &mint_state.get_extension_types().unwrap(),
+    ),
+  )
+  .unwrap();
+
+  let account_kp = Keypair::new();
+  let instructions = [
+    system_instruction::create_account(
+      &payer.pubkey(),
+      &account_kp.pubkey(),
+      Rent::default().minimum_balance(account_len),
+      account_len as u64,
+      &spl_token_2022::ID,
+    ),
+    spl_token_2022::instruction::initialize_account3(
+      &spl_token_2022::ID,
+      &account_kp.pubkey(),
+      mint,
+      owner,
+    )
+    .unwrap(),
+    spl_token_2022::instruction::mint_to(
+      &spl_token_2022::ID,
+      mint,
+      &account_kp.pubkey(),
+      &payer.pubkey(),
+      &[],
+      amount,
+    )
+    .unwrap(),
+  ];
+  process(
+    client,
+    recent_blockhash,
+    &instructions,
+    &[payer, &account_kp],
+  )
+  .await
+  .expect("create token-2022 account");
+  account_kp.pubkey()
+}
+
+pub async fn get_token_balance(client: &BanksClient, address: Pubkey) -> u64 {
+  let account = client.get_account(address).await.unwrap().unwrap();
+  StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+    .unwrap()
+    .base
+    .amount
+}
+
+pub fn share_mint_pubkey(market_pubkey: &Pubkey, side: u8) -> Pubkey {
+  let (share_mint_pubkey, _) = hit_my_bet::state::PredictionMarket::find_share_mint_address(
+    &hit_my_bet::ID,
+    market_pubkey,
+    side,
+  );
+  share_mint_pubkey
+}
+
+/// Creates an empty token account for outcome shares, which only the market can mint.
+pub async fn create_share_account(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  payer: &Keypair,
+  share_mint: &Pubkey,
+  owner: &Pubkey,
+) -> Pubkey {
+  let account_kp = Keypair::new();
+  let instructions = [
+    system_instruction::create_account(
+      &payer.pubkey(),
+      &account_kp.pubkey(),
+      Rent::default().minimum_balance(spl_token::state::Account::LEN),
+      spl_token::state::Account::LEN as u64,
+      &spl_token::ID,
+    ),
+    spl_token::instruction::initialize_account3(
+      &spl_token::ID,
+      &account_kp.pubkey(),
+      share_mint,
+      owner,
+    )
+    .unwrap(),
+  ];
+  process(
+    client,
+    recent_blockhash,
+    &instructions,
+    &[payer, &account_kp],
+  )
+  .await
+  .expect("create share account");
+  account_kp.pubkey()
+}
+
+pub fn vault_pubkey(market_pubkey: &Pubkey) -> Pubkey {
+  let (vault_pubkey, _) = Pubkey::find_program_address(
+    &[
+      hit_my_bet::state::PredictionMarket::VAULT_SEED_PREFIX.as_bytes(),
+      market_pubkey.as_ref(),
+    ],
+    &hit_my_bet::ID,
+  );
+  vault_pubkey
+}
@@ -1,7 +1,10 @@
-use hit_my_bet::error::HitMyBetError;
-use hit_my_bet::instruction::init_prediction_market;
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
 use hit_my_bet::entrypoint::process_instruction;
-use hit_my_bet::state::{PredictionMarket, PROGRAM_VERSION};
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{init_prediction_market, MarketParams};
+use hit_my_bet::state::{MarketKind, MarketPricing, PredictionMarket, PROGRAM_VERSION};
 use solana_program::clock::UnixTimestamp;
 use solana_program::pubkey::Pubkey;
 use solana_program_test::{processor, ProgramTest, ProgramTestBanksClientExt};
@@ -25,18 +28,24 @@ async fn test_success() {
     hit_my_bet::ID,
     processor!(process_instruction),
   );
-
-  test.set_compute_max_units(1_000);
+  add_program_data(&mut test);
 
   let (banks_client, payer, recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+
   let resolver_kp = Keypair::new();
   let instruction = init_prediction_market(
     hit_my_bet::ID,
     payer.pubkey(),
     market_pubkey,
     resolver_kp.pubkey(),
-    MARKET_ID.clone(),
+    config_pubkey(),
+    *MARKET_ID,
     OPEN_UNTIL,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
   );
   let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
   transaction.sign(&[&payer, &resolver_kp], recent_blockhash);
@@ -56,7 +65,10 @@ async fn test_success() {
     PredictionMarket {
       version: PROGRAM_VERSION,
       bump_seed,
+      market_id: *MARKET_ID,
+      creator: payer.pubkey(),
       resolver: resolver_kp.pubkey(),
+      vote_price: VOTE_PRICE,
       open_until: OPEN_UNTIL,
       ..Default::default()
     }
@@ -71,7 +83,7 @@ async fn test_success() {
 //     &[PredictionMarket::SEED_PREFIX.as_bytes(), MARKET_ID],
 //     &hit_my_bet::ID,
 //   );
-//   let mut test = ProgramTest::new(
+//   let test = ProgramTest::new(
 //     "hit_my_bet",
 //     hit_my_bet::ID,
 //     processor!(process_instruction),
@@ -97,14 +109,7 @@ async fn test_success() {
 //   tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
 //
 //   let resolver_kp = Keypair::new();
-//   let instruction = init_prediction_market(
-//     hit_my_bet::ID,
-//     payer.pubkey(),
-//     resolver_kp.pubkey(),
-//     market_pubkey,
-//     MARKET_ID.clone(),
-//     OPEN_UNTIL,
-//   );
+//   let instruction = init_prediction_market(//     hit_my_bet::ID, //     payer.pubkey(), //     resolver_kp.pubkey(), //     market_pubkey, //     MARKET_ID.clone(), //     OPEN_UNTIL, MarketPricing::Parimutuel, //);
 //   let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
 //   recent_blockhash = banks_client
 //     .get_new_latest_blockhash(&recent_blockhash)
@@ -127,18 +132,24 @@ async fn test_already_initialized() {
     hit_my_bet::ID,
     processor!(process_instruction),
   );
-
-  test.set_compute_max_units(1_000);
+  add_program_data(&mut test);
 
   let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+
   let resolver_kp = Keypair::new();
   let instruction = init_prediction_market(
     hit_my_bet::ID,
     payer.pubkey(),
     market_pubkey,
     resolver_kp.pubkey(),
-    MARKET_ID.clone(),
+    config_pubkey(),
+    *MARKET_ID,
     UnixTimestamp::from(1),
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
   );
   {
     let mut transaction =
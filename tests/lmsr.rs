@@ -0,0 +1,240 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::token::{create_share_account, get_token_balance, share_mint_pubkey};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  buy_shares, claim_market, init_prediction_market, make_prediction, resolve_market, sell_shares,
+  MarketParams, UserShareAccounts,
+};
+use hit_my_bet::lmsr;
+use hit_my_bet::state::{MarketKind, MarketPricing, MarketResolution, PredictionMarket, UserVote};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+const LIQUIDITY: u64 = 100;
+
+#[test]
+fn test_cost_function() {
+  let initial_cost = lmsr::cost(0, 0, LIQUIDITY).unwrap();
+  assert!(initial_cost.abs_diff(u128::from(LIQUIDITY) * lmsr::LN_2) < u128::from(LIQUIDITY) * 100);
+  assert_eq!(lmsr::price_yes(0, 0, LIQUIDITY), Some(lmsr::ONE / 2));
+
+  // 100 * (ln(e + 1) - ln(2)) = 62.0114506958...
+  assert_eq!(
+    lmsr::buy_cost((0, 0), (100, 0), LIQUIDITY, 1_000_000_000),
+    Some(62_011_450_696)
+  );
+
+  let price = lmsr::price_yes(30, 10, LIQUIDITY).unwrap();
+  assert!(price > lmsr::ONE / 2);
+  assert!((price + lmsr::price_yes(10, 30, LIQUIDITY).unwrap()).abs_diff(lmsr::ONE) < 10);
+
+  let buy = lmsr::buy_cost((30, 10), (35, 10), LIQUIDITY, VOTE_PRICE).unwrap();
+  let sell = lmsr::sell_return((35, 10), (30, 10), LIQUIDITY, VOTE_PRICE).unwrap();
+  assert!(sell <= buy && buy - sell <= 1);
+
+  // A complete set always costs exactly one unit price.
+  assert_eq!(
+    lmsr::buy_cost((30, 10), (31, 11), LIQUIDITY, VOTE_PRICE),
+    Some(VOTE_PRICE)
+  );
+  assert_eq!(lmsr::cost(0, 0, 0), None);
+}
+
+#[tokio::test]
+async fn test_lmsr_market() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let yes_mint = share_mint_pubkey(&market_pubkey, 0);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Lmsr {
+      liquidity: LIQUIDITY,
+    },
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market_lamports = banks_client.get_balance(market_pubkey).await.unwrap();
+
+  let trader_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    10 * LAMPORTS_PER_SOL,
+  )
+  .await;
+  let share_account = create_share_account(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    &yes_mint,
+    &trader_kp.pubkey(),
+  )
+  .await;
+  let shares = UserShareAccounts {
+    share_mint: yes_mint,
+    token_account: share_account,
+  };
+
+  let instruction = make_prediction(
+    hit_my_bet::ID,
+    trader_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &trader_kp.pubkey()),
+    UserVote::Yes,
+    1,
+    None,
+    Some(shares),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&trader_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidPricingMode.into())
+    )
+  );
+
+  let cost = lmsr::buy_cost((0, 0), (10, 0), LIQUIDITY, VOTE_PRICE).unwrap();
+  for (max_cost, is_ok) in [(cost - 1, false), (cost, true)] {
+    let instruction = buy_shares(
+      hit_my_bet::ID,
+      trader_kp.pubkey(),
+      market_pubkey,
+      UserVote::Yes,
+      10,
+      max_cost,
+      shares,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&trader_kp],
+    )
+    .await;
+    if is_ok {
+      assert!(transaction_result.is_ok());
+    } else {
+      assert_eq!(
+        transaction_result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+          0,
+          InstructionError::Custom(HitMyBetError::SlippageExceeded.into())
+        )
+      );
+    }
+  }
+  assert_eq!(get_token_balance(&banks_client, share_account).await, 10);
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!((market.num_yes, market.num_no), (10, 0));
+  assert_eq!(
+    banks_client.get_balance(market_pubkey).await.unwrap(),
+    market_lamports + cost
+  );
+
+  let proceeds = lmsr::sell_return((10, 0), (6, 0), LIQUIDITY, VOTE_PRICE).unwrap();
+  let instruction = sell_shares(
+    hit_my_bet::ID,
+    trader_kp.pubkey(),
+    market_pubkey,
+    UserVote::Yes,
+    4,
+    proceeds,
+    shares,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&trader_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(get_token_balance(&banks_client, share_account).await, 6);
+  assert_eq!(
+    banks_client.get_balance(market_pubkey).await.unwrap(),
+    market_lamports + cost - proceeds
+  );
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    trader_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &trader_kp.pubkey()),
+    None,
+    Some(shares),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&trader_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+  assert_eq!(balance_before - balance_after, 6 * VOTE_PRICE);
+}
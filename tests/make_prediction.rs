@@ -1,6 +1,9 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
 use hit_my_bet::entrypoint::process_instruction;
-use hit_my_bet::instruction::{init_prediction_market, make_prediction};
-use hit_my_bet::state::{PredictionMarket, UserPrediction, UserVote, VOTE_PRICE};
+use hit_my_bet::instruction::{init_prediction_market, make_prediction, MarketParams};
+use hit_my_bet::state::{MarketKind, MarketPricing, PredictionMarket, UserPrediction, UserVote};
 use solana_program::clock::UnixTimestamp;
 use solana_program::hash::Hash;
 use solana_program::pubkey::Pubkey;
@@ -13,7 +16,7 @@ const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
 
 #[tokio::test]
 async fn test_success() {
-  const NUM_VOTES: u16 = 5;
+  const NUM_VOTES: u64 = 5;
 
   let (market_pubkey, _) = Pubkey::find_program_address(
     &[PredictionMarket::SEED_PREFIX.as_bytes(), MARKET_ID],
@@ -24,10 +27,10 @@ async fn test_success() {
     hit_my_bet::ID,
     processor!(process_instruction),
   );
-
-  test.set_compute_max_units(1_000);
+  add_program_data(&mut test);
 
   let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
 
   let (prediction_pubkey, _) = Pubkey::find_program_address(
     &[
@@ -45,7 +48,7 @@ async fn test_success() {
     payer.pubkey(),
     resolver_kp.pubkey(),
     market_pubkey,
-    MARKET_ID.clone(),
+    *MARKET_ID,
     timestamp + 60,
     &[&payer, &resolver_kp],
     recent_blockhash,
@@ -63,6 +66,9 @@ async fn test_success() {
     prediction_pubkey,
     UserVote::Yes,
     NUM_VOTES,
+    None,
+    None,
+    None,
   );
   let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
   recent_blockhash = banks_client
@@ -79,7 +85,7 @@ async fn test_success() {
     .await
     .unwrap();
   let balance_diff = get_balance(&banks_client, market_pubkey).await - balance_before;
-  assert_eq!(balance_diff, VOTE_PRICE * (u64::from(NUM_VOTES)));
+  assert_eq!(balance_diff, VOTE_PRICE * NUM_VOTES);
 
   let account: Result<_, _> = banks_client.get_account(market_pubkey).await;
   assert!(account.is_ok());
@@ -87,8 +93,8 @@ async fn test_success() {
   let account_data = account.unwrap().unwrap().data;
   let market: PredictionMarket =
     borsh::BorshDeserialize::deserialize(&mut account_data.as_slice()).unwrap();
-  assert_eq!(market.num_yes, u64::from(NUM_VOTES));
-  assert_eq!(market.balance_yes, VOTE_PRICE * (u64::from(NUM_VOTES)));
+  assert_eq!(market.num_yes, NUM_VOTES);
+  assert_eq!(market.balance_yes, VOTE_PRICE * NUM_VOTES);
 
   let account: Result<_, _> = banks_client.get_account(prediction_pubkey).await;
   assert!(account.is_ok());
@@ -96,7 +102,7 @@ async fn test_success() {
   let account_data = account.unwrap().unwrap().data;
   let prediction: UserPrediction =
     borsh::BorshDeserialize::deserialize(&mut account_data.as_slice()).unwrap();
-  assert_eq!(prediction.num_votes_yes, u64::from(NUM_VOTES));
+  assert_eq!(prediction.num_votes_yes, NUM_VOTES);
 }
 
 async fn get_balance(client: &BanksClient, address: Pubkey) -> u64 {
@@ -126,8 +132,13 @@ fn init_prediction_market_tx(
     payer,
     market_pubkey,
     resolver,
+    config_pubkey(),
     market_id,
     open_until,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
   );
   let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer));
   transaction.sign(signers, blockhash);
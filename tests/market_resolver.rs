@@ -0,0 +1,224 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  accept_market_resolver, init_prediction_market, propose_market_resolver, resolve_market,
+  MarketParams,
+};
+use hit_my_bet::state::{MarketKind, MarketPricing, MarketResolution, PredictionMarket};
+use solana_program::clock::UnixTimestamp;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, BanksClient, ProgramTest, ProgramTestBanksClientExt};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[tokio::test]
+async fn test_rotate_resolver() {
+  let (mut banks_client, payer, mut recent_blockhash, market_pubkey, resolver_kp) =
+    setup_market().await;
+  let new_resolver_kp = Keypair::new();
+
+  let instruction = propose_market_resolver(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    config_pubkey(),
+    new_resolver_kp.pubkey(),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let market = get_market(&banks_client, market_pubkey).await;
+  assert_eq!(market.resolver, resolver_kp.pubkey());
+  assert_eq!(market.pending_resolver, Some(new_resolver_kp.pubkey()));
+
+  let instruction = accept_market_resolver(hit_my_bet::ID, new_resolver_kp.pubkey(), market_pubkey);
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &new_resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let market = get_market(&banks_client, market_pubkey).await;
+  assert_eq!(market.resolver, new_resolver_kp.pubkey());
+  assert_eq!(market.pending_resolver, None);
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidResolver.into())
+    )
+  );
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    new_resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &new_resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+}
+
+#[tokio::test]
+async fn test_unauthorized_takeover() {
+  let (mut banks_client, payer, mut recent_blockhash, market_pubkey, resolver_kp) =
+    setup_market().await;
+  let attacker_kp = Keypair::new();
+
+  let instruction = propose_market_resolver(
+    hit_my_bet::ID,
+    attacker_kp.pubkey(),
+    market_pubkey,
+    config_pubkey(),
+    attacker_kp.pubkey(),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &attacker_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidResolverAuthority.into())
+    )
+  );
+
+  let instruction = accept_market_resolver(hit_my_bet::ID, attacker_kp.pubkey(), market_pubkey);
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &attacker_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidResolverAuthority.into())
+    )
+  );
+
+  let market = get_market(&banks_client, market_pubkey).await;
+  assert_eq!(market.resolver, resolver_kp.pubkey());
+  assert_eq!(market.pending_resolver, None);
+}
+
+async fn setup_market() -> (BanksClient, Keypair, Hash, Pubkey, Keypair) {
+  let (market_pubkey, _) = Pubkey::find_program_address(
+    &[PredictionMarket::SEED_PREFIX.as_bytes(), MARKET_ID],
+    &hit_my_bet::ID,
+  );
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  (
+    banks_client,
+    payer,
+    recent_blockhash,
+    market_pubkey,
+    resolver_kp,
+  )
+}
+
+async fn process(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  instruction: Instruction,
+  signers: &[&Keypair],
+) -> Result<(), solana_program_test::BanksClientError> {
+  *recent_blockhash = client
+    .get_new_latest_blockhash(recent_blockhash)
+    .await
+    .unwrap();
+  let mut transaction = Transaction::new_with_payer(&[instruction], Some(&signers[0].pubkey()));
+  transaction.sign(signers, *recent_blockhash);
+  client.process_transaction(transaction).await
+}
+
+async fn get_market(client: &BanksClient, market_pubkey: Pubkey) -> PredictionMarket {
+  let account = client.get_account(market_pubkey).await.unwrap().unwrap();
+  borsh::BorshDeserialize::deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+async fn get_unix_timestamp(client: &BanksClient) -> UnixTimestamp {
+  let clock = client
+    .get_account(solana_program::clock::sysvar::ID)
+    .await
+    .unwrap()
+    .unwrap();
+  UnixTimestamp::from_le_bytes(clock.data[32..].as_ref().try_into().unwrap())
+}
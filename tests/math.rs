@@ -0,0 +1,168 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::{funded_keypair, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  claim_market, init_prediction_market, make_prediction, resolve_market, MarketParams,
+};
+use hit_my_bet::math;
+use hit_my_bet::state::{MarketKind, MarketPricing, MarketResolution, UserVote};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[test]
+fn test_checked_math() {
+  assert_eq!(math::add(u64::MAX, 1), Err(HitMyBetError::MathOverflow));
+  assert_eq!(math::sub(0, 1), Err(HitMyBetError::MathOverflow));
+  assert_eq!(math::mul(u64::MAX, 2), Err(HitMyBetError::MathOverflow));
+  assert_eq!(math::sum([u64::MAX - 1, 1]), Ok(u64::MAX));
+  assert_eq!(math::sum([u64::MAX, 1]), Err(HitMyBetError::MathOverflow));
+
+  // The product is taken in u128, so only a quotient that does not fit is an overflow.
+  assert_eq!(math::mul_div(u64::MAX, u64::MAX, u64::MAX), Ok(u64::MAX));
+  assert_eq!(
+    math::mul_div(u64::MAX, 2, 1),
+    Err(HitMyBetError::MathOverflow)
+  );
+  assert_eq!(math::mul_div(1, 1, 0), Err(HitMyBetError::MathOverflow));
+
+  // Shares round down and leave the remainder in the pool.
+  assert_eq!(math::pool_share(100, 1, 3), Ok(33));
+  assert_eq!(math::pool_share(100, 3, 3), Ok(100));
+  assert_eq!(math::pool_share(100, 0, 0), Ok(0));
+  assert_eq!(
+    math::pool_share(100, 1, 0),
+    Err(HitMyBetError::MathOverflow)
+  );
+
+  assert_eq!(math::bps(1_000, 200), Ok(20));
+  assert_eq!(math::bps(49, 200), Ok(0));
+}
+
+#[test]
+fn test_scalar_payout() {
+  // A value at 3/4 of the range gives the long side 3/4 of the 400 pool.
+  assert_eq!(
+    math::scalar_payout((100, 300), (2, 4), (1, 0), (0, 100), 75),
+    Ok(150)
+  );
+  assert_eq!(
+    math::scalar_payout((100, 300), (2, 4), (0, 1), (0, 100), 75),
+    Ok(25)
+  );
+  assert_eq!(
+    math::scalar_payout((100, 300), (2, 4), (2, 4), (-100, 100), 100),
+    Ok(400)
+  );
+  assert_eq!(
+    math::scalar_payout((u64::MAX, 1), (1, 1), (1, 1), (0, 1), 1),
+    Err(HitMyBetError::MathOverflow)
+  );
+}
+
+#[tokio::test]
+async fn test_empty_winning_side_refund() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let voter_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    10 * LAMPORTS_PER_SOL,
+  )
+  .await;
+  let instruction = make_prediction(
+    hit_my_bet::ID,
+    voter_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+    UserVote::No,
+    3,
+    None,
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voter_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // Nobody voted yes, so the losing no vote is refunded in full.
+  let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    voter_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voter_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+  assert_eq!(balance_before - balance_after, 3 * VOTE_PRICE);
+}
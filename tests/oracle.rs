@@ -0,0 +1,473 @@
+mod helpers;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config};
+use helpers::{get_state, get_unix_timestamp, market_pubkey, process, warp_clock};
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  init_prediction_market, resolve_from_oracle, resolve_market, resolve_market_admin, MarketParams,
+};
+use hit_my_bet::oracle::{PriceFeed, PriceReading, PythPriceFeed, MAX_PRICE_AGE};
+use hit_my_bet::processor::process_instruction_with_price_feed;
+use hit_my_bet::state::{
+  MarketKind, MarketPricing, MarketResolution, OracleCondition, PredictionMarket, PriceComparison,
+  ResolutionStatus,
+};
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::UnixTimestamp;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+/// 100,000.00 with the feed's exponent of -2.
+const THRESHOLD: i64 = 10_000_000;
+
+const EXPONENT: i32 = -2;
+
+const FEED_ID: [u8; 32] = [7; 32];
+
+/// Borsh data of the price feed accounts of the tests.
+#[derive(BorshDeserialize, BorshSerialize)]
+struct MockPriceFeed {
+  feed_id: [u8; 32],
+  price: i64,
+  confidence: u64,
+  exponent: i32,
+  publish_time: UnixTimestamp,
+}
+
+impl PriceFeed for MockPriceFeed {
+  fn read(price_feed_info: &AccountInfo) -> Result<PriceReading, ProgramError> {
+    let feed: MockPriceFeed =
+      BorshDeserialize::deserialize(&mut price_feed_info.data.borrow().as_ref())
+        .map_err(|_| ProgramError::from(HitMyBetError::InvalidPriceFeed))?;
+    Ok(PriceReading {
+      feed_id: feed.feed_id,
+      price: feed.price,
+      confidence: feed.confidence,
+      exponent: feed.exponent,
+      publish_time: feed.publish_time,
+    })
+  }
+}
+
+fn mock_feed_account(price: i64, confidence: u64, publish_time: UnixTimestamp) -> Account {
+  mock_feed_account_with_id(FEED_ID, price, confidence, publish_time)
+}
+
+fn mock_feed_account_with_id(
+  feed_id: [u8; 32],
+  price: i64,
+  confidence: u64,
+  publish_time: UnixTimestamp,
+) -> Account {
+  let feed = MockPriceFeed {
+    feed_id,
+    price,
+    confidence,
+    exponent: EXPONENT,
+    publish_time,
+  };
+  Account {
+    lamports: 1_000_000,
+    data: borsh::to_vec(&feed).unwrap(),
+    owner: Pubkey::new_unique(),
+    executable: false,
+    rent_epoch: 0,
+  }
+}
+
+/// `PriceUpdateV2` account data with `verification_level` encoded as borsh, `[1]` for `Full` and
+/// `[0, num_signatures]` for `Partial`.
+fn pyth_price_update_data(verification_level: &[u8], price: i64, publish_time: i64) -> Vec<u8> {
+  let mut data = PythPriceFeed::DISCRIMINATOR.to_vec();
+  data.extend_from_slice(Pubkey::new_unique().as_ref());
+  data.extend_from_slice(verification_level);
+  data.extend_from_slice(&FEED_ID);
+  data.extend_from_slice(&price.to_le_bytes());
+  data.extend_from_slice(&1_000u64.to_le_bytes());
+  data.extend_from_slice(&EXPONENT.to_le_bytes());
+  data.extend_from_slice(&publish_time.to_le_bytes());
+  data.extend_from_slice(&(publish_time - 1).to_le_bytes());
+  data.extend_from_slice(&price.to_le_bytes());
+  data.extend_from_slice(&1_000u64.to_le_bytes());
+  data.extend_from_slice(&42u64.to_le_bytes());
+  data
+}
+
+fn read_pyth_feed(owner: &Pubkey, mut data: Vec<u8>) -> Result<PriceReading, ProgramError> {
+  let key = Pubkey::new_unique();
+  let mut lamports = 1_000_000;
+  let price_feed_info = AccountInfo::new(
+    &key,
+    false,
+    false,
+    &mut lamports,
+    &mut data,
+    owner,
+    false,
+    0,
+  );
+  PythPriceFeed::read(&price_feed_info)
+}
+
+fn custom_error(error: HitMyBetError) -> TransactionError {
+  TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+}
+
+#[tokio::test]
+async fn test_resolve_from_oracle() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let price_feed_pubkey = Pubkey::new_unique();
+  let other_feed_pubkey = Pubkey::new_unique();
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction_with_price_feed::<MockPriceFeed>),
+  );
+  add_program_data(&mut test);
+  test.add_account(price_feed_pubkey, mock_feed_account(THRESHOLD + 1, 0, 0));
+  test.add_account(other_feed_pubkey, mock_feed_account(THRESHOLD + 1, 0, 0));
+
+  let mut context = test.start_with_context().await;
+  let payer = context.payer.insecure_clone();
+  let mut recent_blockhash = context.last_blockhash;
+  setup_program_config(&context.banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&context.banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let init_market = |kind, resolve_at| {
+    init_prediction_market(
+      hit_my_bet::ID,
+      payer.pubkey(),
+      market_pubkey,
+      resolver_kp.pubkey(),
+      config_pubkey(),
+      *MARKET_ID,
+      timestamp + 60,
+      kind,
+      MarketPricing::Parimutuel,
+      MarketParams {
+        oracle: Some(OracleCondition {
+          price_feed: price_feed_pubkey,
+          feed_id: FEED_ID,
+          comparison: PriceComparison::Above,
+          threshold: THRESHOLD,
+          exponent: EXPONENT,
+          resolve_at,
+        }),
+        ..Default::default()
+      },
+      None,
+    )
+  };
+
+  for (kind, resolve_at, error) in [
+    (
+      MarketKind::Categorical { num_outcomes: 3 },
+      timestamp + 120,
+      HitMyBetError::InvalidMarketKind,
+    ),
+    (
+      MarketKind::Binary,
+      timestamp + 30,
+      HitMyBetError::InvalidOracleCondition,
+    ),
+  ] {
+    let transaction_result = process(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &[init_market(kind, resolve_at)],
+      &[&payer, &resolver_kp],
+    )
+    .await;
+    assert_eq!(
+      transaction_result.unwrap_err().unwrap(),
+      custom_error(error)
+    );
+  }
+
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[init_market(MarketKind::Binary, timestamp + 120)],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // Oracle markets have no human resolver.
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::No,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::InvalidResolver)
+  );
+
+  let instruction = resolve_from_oracle(hit_my_bet::ID, market_pubkey, price_feed_pubkey);
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction.clone()],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::StalePrice)
+  );
+
+  warp_clock(&mut context, 120).await;
+  let resolve_at = timestamp + 120;
+
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[resolve_from_oracle(
+      hit_my_bet::ID,
+      market_pubkey,
+      other_feed_pubkey,
+    )],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::InvalidPriceFeed)
+  );
+
+  // A price published after the window following resolve_at is too late, however fresh.
+  warp_clock(&mut context, MAX_PRICE_AGE + 1).await;
+  context.set_account(
+    &price_feed_pubkey,
+    &AccountSharedData::from(mock_feed_account(
+      THRESHOLD + 1,
+      u64::try_from(THRESHOLD).unwrap() / 200,
+      resolve_at + MAX_PRICE_AGE + 1,
+    )),
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction.clone()],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::StalePrice)
+  );
+  let publish_time = resolve_at + MAX_PRICE_AGE;
+
+  // A confidence interval above 1% of the price is too uncertain.
+  context.set_account(
+    &price_feed_pubkey,
+    &AccountSharedData::from(mock_feed_account(
+      THRESHOLD + 1,
+      u64::try_from(THRESHOLD).unwrap() / 50,
+      publish_time,
+    )),
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction.clone()],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::PriceTooUncertain)
+  );
+
+  // A price of another feed in the same account does not count.
+  context.set_account(
+    &price_feed_pubkey,
+    &AccountSharedData::from(mock_feed_account_with_id(
+      [8; 32],
+      THRESHOLD + 1,
+      u64::try_from(THRESHOLD).unwrap() / 200,
+      publish_time,
+    )),
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction.clone()],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::InvalidPriceFeed)
+  );
+
+  context.set_account(
+    &price_feed_pubkey,
+    &AccountSharedData::from(mock_feed_account(
+      THRESHOLD + 1,
+      u64::try_from(THRESHOLD).unwrap() / 200,
+      publish_time,
+    )),
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction.clone()],
+    &[&payer],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+  assert_eq!(market.resolution, MarketResolution::Yes);
+  assert_eq!(market.resolution_status, ResolutionStatus::Final);
+
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::MarketIsResolved)
+  );
+}
+
+#[tokio::test]
+async fn test_admin_resolves_unpriced_market() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let price_feed_pubkey = Pubkey::new_unique();
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction_with_price_feed::<MockPriceFeed>),
+  );
+  add_program_data(&mut test);
+  test.add_account(price_feed_pubkey, mock_feed_account(THRESHOLD + 1, 0, 0));
+
+  let mut context = test.start_with_context().await;
+  let payer = context.payer.insecure_clone();
+  let mut recent_blockhash = context.last_blockhash;
+  let admin_kp = setup_program_config(&context.banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&context.banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams {
+      oracle: Some(OracleCondition {
+        price_feed: price_feed_pubkey,
+        feed_id: FEED_ID,
+        comparison: PriceComparison::Above,
+        threshold: THRESHOLD,
+        exponent: EXPONENT,
+        resolve_at: timestamp + 120,
+      }),
+      ..Default::default()
+    },
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // Betting has closed, but a price published after resolve_at can still come in.
+  warp_clock(&mut context, 120 + MAX_PRICE_AGE).await;
+  let instruction = resolve_market_admin(
+    hit_my_bet::ID,
+    admin_kp.pubkey(),
+    market_pubkey,
+    config_pubkey(),
+    MarketResolution::No,
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction.clone()],
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::MarketIsOpen)
+  );
+
+  warp_clock(&mut context, 1).await;
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+  assert_eq!(market.resolution, MarketResolution::No);
+  assert_eq!(market.resolution_status, ResolutionStatus::Final);
+}
+
+#[test]
+fn test_pyth_price_feed() {
+  let reading = read_pyth_feed(
+    &PythPriceFeed::OWNER,
+    pyth_price_update_data(&[1], THRESHOLD, 1_700_000_000),
+  );
+  assert_eq!(
+    reading,
+    Ok(PriceReading {
+      feed_id: FEED_ID,
+      price: THRESHOLD,
+      confidence: 1_000,
+      exponent: EXPONENT,
+      publish_time: 1_700_000_000,
+    })
+  );
+
+  // Partially verified updates are rejected.
+  let reading = read_pyth_feed(
+    &PythPriceFeed::OWNER,
+    pyth_price_update_data(&[0, 5], THRESHOLD, 1_700_000_000),
+  );
+  assert_eq!(reading, Err(HitMyBetError::InvalidPriceFeed.into()));
+
+  // So are accounts of any other program.
+  let reading = read_pyth_feed(
+    &Pubkey::new_unique(),
+    pyth_price_update_data(&[1], THRESHOLD, 1_700_000_000),
+  );
+  assert_eq!(reading, Err(HitMyBetError::InvalidPriceFeed.into()));
+}
@@ -0,0 +1,305 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::token::{create_share_account, get_token_balance, share_mint_pubkey};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  cancel_order, init_prediction_market, make_prediction, match_orders, place_order, MarketParams,
+  UserShareAccounts,
+};
+use hit_my_bet::state::{MarketKind, MarketPricing, OrderBook, OrderSide, UserVote};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[tokio::test]
+async fn test_order_book() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let yes_mint = share_mint_pubkey(&market_pubkey, 0);
+  let (order_book_pubkey, _) = OrderBook::find_address(&hit_my_bet::ID, &market_pubkey);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let mut keypairs = Vec::new();
+  let mut share_accounts = Vec::new();
+  for _ in 0..2 {
+    let keypair = funded_keypair(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      10 * LAMPORTS_PER_SOL,
+    )
+    .await;
+    share_accounts.push(UserShareAccounts {
+      share_mint: yes_mint,
+      token_account: create_share_account(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &payer,
+        &yes_mint,
+        &keypair.pubkey(),
+      )
+      .await,
+    });
+    keypairs.push(keypair);
+  }
+  let [seller_kp, buyer_kp] = &keypairs[..] else {
+    unreachable!()
+  };
+  let [seller_shares, buyer_shares] = share_accounts[..] else {
+    unreachable!()
+  };
+
+  let instruction = make_prediction(
+    hit_my_bet::ID,
+    seller_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &seller_kp.pubkey()),
+    UserVote::Yes,
+    10,
+    None,
+    Some(seller_shares),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[seller_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // Ask 6 shares at half the vote price, bid for 4 above it and for 2 below it.
+  const ASK_PRICE: u64 = VOTE_PRICE / 2;
+  const BID_PRICE: u64 = VOTE_PRICE * 6 / 10;
+  const LOW_BID_PRICE: u64 = VOTE_PRICE * 4 / 10;
+  let instruction = place_order(
+    hit_my_bet::ID,
+    seller_kp.pubkey(),
+    market_pubkey,
+    OrderSide::Ask,
+    ASK_PRICE,
+    6,
+    None,
+    Some(seller_shares),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[seller_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    get_token_balance(&banks_client, seller_shares.token_account).await,
+    4
+  );
+
+  let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+  for (price, quantity) in [(BID_PRICE, 4), (LOW_BID_PRICE, 2)] {
+    let instruction = place_order(
+      hit_my_bet::ID,
+      buyer_kp.pubkey(),
+      market_pubkey,
+      OrderSide::Bid,
+      price,
+      quantity,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[buyer_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+  }
+  let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+  assert_eq!(
+    balance_after - balance_before,
+    4 * BID_PRICE + 2 * LOW_BID_PRICE
+  );
+
+  let seller_balance = banks_client.get_balance(seller_kp.pubkey()).await.unwrap();
+  for (bid_shares, is_ok) in [(seller_shares, false), (buyer_shares, true)] {
+    let instruction = match_orders(
+      hit_my_bet::ID,
+      payer.pubkey(),
+      market_pubkey,
+      seller_kp.pubkey(),
+      bid_shares,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&payer],
+    )
+    .await;
+    if is_ok {
+      assert!(transaction_result.is_ok());
+    } else {
+      assert_eq!(
+        transaction_result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+          0,
+          InstructionError::Custom(HitMyBetError::InvalidOrder.into())
+        )
+      );
+    }
+  }
+  assert_eq!(
+    banks_client.get_balance(seller_kp.pubkey()).await.unwrap() - seller_balance,
+    4 * BID_PRICE
+  );
+  assert_eq!(
+    get_token_balance(&banks_client, buyer_shares.token_account).await,
+    4
+  );
+
+  let order_book: OrderBook = get_state(&banks_client, order_book_pubkey).await;
+  assert_eq!(order_book.orders.len(), 2);
+  let ask = order_book.orders[order_book.best_ask().unwrap()];
+  let bid = order_book.orders[order_book.best_bid().unwrap()];
+  assert_eq!((ask.price, ask.quantity), (ASK_PRICE, 2));
+  assert_eq!((bid.price, bid.quantity), (LOW_BID_PRICE, 2));
+
+  let instruction = match_orders(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    seller_kp.pubkey(),
+    buyer_shares,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::OrdersNotCrossed.into())
+    )
+  );
+
+  let instruction = cancel_order(
+    hit_my_bet::ID,
+    seller_kp.pubkey(),
+    seller_kp.pubkey(),
+    market_pubkey,
+    bid.order_id,
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[seller_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::OrderNotFound.into())
+    )
+  );
+
+  let instruction = cancel_order(
+    hit_my_bet::ID,
+    seller_kp.pubkey(),
+    seller_kp.pubkey(),
+    market_pubkey,
+    ask.order_id,
+    None,
+    Some(seller_shares),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[seller_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    get_token_balance(&banks_client, seller_shares.token_account).await,
+    6
+  );
+
+  let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+  let instruction = cancel_order(
+    hit_my_bet::ID,
+    buyer_kp.pubkey(),
+    buyer_kp.pubkey(),
+    market_pubkey,
+    bid.order_id,
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[buyer_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+  assert_eq!(balance_before - balance_after, 2 * LOW_BID_PRICE);
+
+  let order_book: OrderBook = get_state(&banks_client, order_book_pubkey).await;
+  assert!(order_book.orders.is_empty());
+  assert_eq!(order_book.next_order_id, 3);
+}
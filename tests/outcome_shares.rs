@@ -0,0 +1,254 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::token::{create_share_account, get_token_balance, share_mint_pubkey};
+use helpers::{funded_keypair, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  claim_market, init_prediction_market, make_prediction, resolve_market, MarketParams,
+  UserShareAccounts,
+};
+use hit_my_bet::state::{MarketKind, MarketPricing, MarketResolution, UserVote};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::program_pack::Pack;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[tokio::test]
+async fn test_outcome_shares() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let yes_mint = share_mint_pubkey(&market_pubkey, 0);
+  let no_mint = share_mint_pubkey(&market_pubkey, 1);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  for share_mint in [yes_mint, no_mint] {
+    let account = banks_client.get_account(share_mint).await.unwrap().unwrap();
+    let mint = spl_token::state::Mint::unpack(&account.data).unwrap();
+    assert_eq!(mint.mint_authority, Some(market_pubkey).into());
+    assert_eq!(mint.decimals, 0);
+    assert_eq!(mint.supply, 0);
+  }
+
+  let yes_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  let no_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  let holder_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  let yes_shares = create_share_account(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    &yes_mint,
+    &yes_kp.pubkey(),
+  )
+  .await;
+  let holder_shares = create_share_account(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    &yes_mint,
+    &holder_kp.pubkey(),
+  )
+  .await;
+
+  let instruction = make_prediction(
+    hit_my_bet::ID,
+    yes_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &yes_kp.pubkey()),
+    UserVote::No,
+    2,
+    None,
+    Some(UserShareAccounts {
+      share_mint: yes_mint,
+      token_account: yes_shares,
+    }),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&yes_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidShareMint.into())
+    )
+  );
+
+  let instruction = make_prediction(
+    hit_my_bet::ID,
+    yes_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &yes_kp.pubkey()),
+    UserVote::Yes,
+    2,
+    None,
+    Some(UserShareAccounts {
+      share_mint: yes_mint,
+      token_account: yes_shares,
+    }),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&yes_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(get_token_balance(&banks_client, yes_shares).await, 2);
+  assert!(banks_client
+    .get_account(user_prediction_pubkey(&market_pubkey, &yes_kp.pubkey()))
+    .await
+    .unwrap()
+    .is_none());
+
+  let instruction = make_prediction(
+    hit_my_bet::ID,
+    no_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &no_kp.pubkey()),
+    UserVote::No,
+    2,
+    None,
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&no_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // Shares move like any other token.
+  let instruction = spl_token::instruction::transfer(
+    &spl_token::ID,
+    &yes_shares,
+    &holder_shares,
+    &yes_kp.pubkey(),
+    &[],
+    1,
+  )
+  .unwrap();
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&yes_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  for (claimer, token_account) in [(&holder_kp, holder_shares), (&yes_kp, yes_shares)] {
+    let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+    let instruction = claim_market(
+      hit_my_bet::ID,
+      claimer.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &claimer.pubkey()),
+      None,
+      Some(UserShareAccounts {
+        share_mint: yes_mint,
+        token_account,
+      }),
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[claimer],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+
+    let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+    assert_eq!(balance_before - balance_after, 2 * VOTE_PRICE);
+    assert_eq!(get_token_balance(&banks_client, token_account).await, 0);
+  }
+
+  let account = banks_client.get_account(yes_mint).await.unwrap().unwrap();
+  assert_eq!(
+    spl_token::state::Mint::unpack(&account.data)
+      .unwrap()
+      .supply,
+    0
+  );
+}
@@ -0,0 +1,412 @@
+mod helpers;
+
+use helpers::program_config::{
+  add_program_data, config_pubkey, setup_program_config, CREATE_MARKET_FEE, VOTE_PRICE,
+};
+use helpers::{get_unix_timestamp, warp_clock};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  accept_admin, cancel_admin_proposal, init_prediction_market, initialize_config, propose_admin,
+  resolve_market_admin, update_config, MarketParams,
+};
+use hit_my_bet::state::{
+  MarketKind, MarketPricing, MarketResolution, PredictionMarket, ProgramConfig, PROGRAM_VERSION,
+};
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, BanksClient, ProgramTest, ProgramTestBanksClientExt};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[tokio::test]
+async fn test_initialize_config() {
+  let (mut banks_client, payer, mut recent_blockhash) = program_test().start().await;
+
+  // Only the upgrade authority can take the admin role.
+  let other_admin_kp = Keypair::new();
+  let instruction = initialize_config(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    config_pubkey(),
+    other_admin_kp.pubkey(),
+    CREATE_MARKET_FEE,
+    VOTE_PRICE,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &other_admin_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidUpgradeAuthority.into())
+    )
+  );
+
+  let admin_kp = setup_program_config(&banks_client, &payer, recent_blockhash).await;
+
+  let (_, bump_seed) =
+    Pubkey::find_program_address(&[ProgramConfig::SEED_PREFIX.as_bytes()], &hit_my_bet::ID);
+  assert_eq!(
+    get_config(&banks_client).await,
+    ProgramConfig {
+      version: PROGRAM_VERSION,
+      bump_seed,
+      admin: admin_kp.pubkey(),
+      pending_admin: None,
+      create_market_fee: CREATE_MARKET_FEE,
+      vote_price: VOTE_PRICE,
+    }
+  );
+
+  let instruction = initialize_config(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    config_pubkey(),
+    admin_kp.pubkey(),
+    0,
+    VOTE_PRICE,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::AlreadyInitialized.into())
+    )
+  );
+}
+
+#[tokio::test]
+async fn test_update_config() {
+  let (mut banks_client, payer, mut recent_blockhash) = program_test().start().await;
+  let admin_kp = setup_program_config(&banks_client, &payer, recent_blockhash).await;
+
+  let instruction = update_config(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    config_pubkey(),
+    0,
+    VOTE_PRICE * 2,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidAdmin.into())
+    )
+  );
+
+  // Votes must cost something.
+  let instruction = update_config(hit_my_bet::ID, admin_kp.pubkey(), config_pubkey(), 0, 0);
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidBetLimits.into())
+    )
+  );
+
+  let instruction = update_config(
+    hit_my_bet::ID,
+    admin_kp.pubkey(),
+    config_pubkey(),
+    0,
+    VOTE_PRICE * 2,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let config = get_config(&banks_client).await;
+  assert_eq!(config.create_market_fee, 0);
+  assert_eq!(config.vote_price, VOTE_PRICE * 2);
+}
+
+#[tokio::test]
+async fn test_transfer_admin() {
+  let (mut banks_client, payer, mut recent_blockhash) = program_test().start().await;
+  let admin_kp = setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let new_admin_kp = Keypair::new();
+
+  let instruction = propose_admin(
+    hit_my_bet::ID,
+    admin_kp.pubkey(),
+    config_pubkey(),
+    Pubkey::new_unique(),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let instruction = cancel_admin_proposal(hit_my_bet::ID, admin_kp.pubkey(), config_pubkey());
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(get_config(&banks_client).await.pending_admin, None);
+
+  let instruction = propose_admin(
+    hit_my_bet::ID,
+    admin_kp.pubkey(),
+    config_pubkey(),
+    new_admin_kp.pubkey(),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let instruction = accept_admin(hit_my_bet::ID, payer.pubkey(), config_pubkey());
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidAdmin.into())
+    )
+  );
+
+  let instruction = accept_admin(hit_my_bet::ID, new_admin_kp.pubkey(), config_pubkey());
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &new_admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let config = get_config(&banks_client).await;
+  assert_eq!(config.admin, new_admin_kp.pubkey());
+  assert_eq!(config.pending_admin, None);
+
+  let instruction = update_config(
+    hit_my_bet::ID,
+    admin_kp.pubkey(),
+    config_pubkey(),
+    0,
+    VOTE_PRICE,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidAdmin.into())
+    )
+  );
+}
+
+#[tokio::test]
+async fn test_resolve_market_admin() {
+  let (market_pubkey, _) = Pubkey::find_program_address(
+    &[PredictionMarket::SEED_PREFIX.as_bytes(), MARKET_ID],
+    &hit_my_bet::ID,
+  );
+  let mut context = program_test().start_with_context().await;
+  let payer = context.payer.insecure_clone();
+  let mut recent_blockhash = context.last_blockhash;
+  let admin_kp = setup_program_config(&context.banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&context.banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let instruction = resolve_market_admin(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    config_pubkey(),
+    MarketResolution::No,
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    instruction,
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidResolver.into())
+    )
+  );
+
+  let resolve = |resolution| {
+    resolve_market_admin(
+      hit_my_bet::ID,
+      admin_kp.pubkey(),
+      market_pubkey,
+      config_pubkey(),
+      resolution,
+      None,
+    )
+  };
+
+  // The admin waits for betting to close.
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    resolve(MarketResolution::No),
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::MarketIsOpen.into())
+    )
+  );
+
+  warp_clock(&mut context, 60).await;
+
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    resolve(MarketResolution::No),
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let account = context
+    .banks_client
+    .get_account(market_pubkey)
+    .await
+    .unwrap()
+    .unwrap();
+  let market: PredictionMarket =
+    borsh::BorshDeserialize::deserialize(&mut account.data.as_slice()).unwrap();
+  assert_eq!(market.resolution, MarketResolution::No);
+
+  // A final resolution cannot be overwritten.
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    resolve(MarketResolution::Yes),
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::MarketIsResolved.into())
+    )
+  );
+}
+
+fn program_test() -> ProgramTest {
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+  test
+}
+
+async fn process(
+  client: &mut BanksClient,
+  recent_blockhash: &mut Hash,
+  instruction: Instruction,
+  signers: &[&Keypair],
+) -> Result<(), solana_program_test::BanksClientError> {
+  *recent_blockhash = client
+    .get_new_latest_blockhash(recent_blockhash)
+    .await
+    .unwrap();
+  let mut transaction = Transaction::new_with_payer(&[instruction], Some(&signers[0].pubkey()));
+  transaction.sign(signers, *recent_blockhash);
+  client.process_transaction(transaction).await
+}
+
+async fn get_config(client: &BanksClient) -> ProgramConfig {
+  let account = client.get_account(config_pubkey()).await.unwrap().unwrap();
+  borsh::BorshDeserialize::deserialize(&mut account.data.as_slice()).unwrap()
+}
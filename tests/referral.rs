@@ -0,0 +1,352 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::token::{create_mint, create_token_account, get_token_balance, vault_pubkey};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  init_prediction_market, make_prediction, withdraw_referral_fees, CollateralAccounts,
+  MarketParams, UserCollateralAccounts,
+};
+use hit_my_bet::state::{
+  MarketFees, MarketKind, MarketPricing, PredictionMarket, ReferrerAccount, ReferrerMintFees,
+  UserVote,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::rent::Rent;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[tokio::test]
+async fn test_referral_fees() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let init_market = |referral_fee_bps| {
+    init_prediction_market(
+      hit_my_bet::ID,
+      payer.pubkey(),
+      market_pubkey,
+      resolver_kp.pubkey(),
+      config_pubkey(),
+      *MARKET_ID,
+      timestamp + 60,
+      MarketKind::Binary,
+      MarketPricing::Parimutuel,
+      MarketParams {
+        fees: Some(MarketFees {
+          referral_fee_bps,
+          ..Default::default()
+        }),
+        ..Default::default()
+      },
+      None,
+    )
+  };
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[init_market(MarketFees::MAX_REFERRAL_BPS + 1)],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidFees.into())
+    )
+  );
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[init_market(100)],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let referrer_kp = Keypair::new();
+  let (referrer_account_pubkey, _) =
+    ReferrerAccount::find_address(&hit_my_bet::ID, &referrer_kp.pubkey());
+  let market_before = banks_client.get_balance(market_pubkey).await.unwrap();
+
+  // Two referred bets of 3 votes each, the first one creating the referrer account.
+  for _ in 0..2 {
+    let voter_kp = funded_keypair(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      10 * LAMPORTS_PER_SOL,
+    )
+    .await;
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      UserVote::Yes,
+      3,
+      None,
+      None,
+      Some(referrer_kp.pubkey()),
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+  }
+
+  // The referral fee is paid on top of the bets, so the pool holds the full bets.
+  let referral_fee = 3 * VOTE_PRICE / 100;
+  assert_eq!(
+    banks_client.get_balance(market_pubkey).await.unwrap() - market_before,
+    6 * VOTE_PRICE
+  );
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.balance_yes, 6 * VOTE_PRICE);
+
+  let referrer_rent = Rent::default().minimum_balance(ReferrerAccount::LEN);
+  assert_eq!(
+    banks_client
+      .get_balance(referrer_account_pubkey)
+      .await
+      .unwrap(),
+    referrer_rent + 2 * referral_fee
+  );
+  let referrer_account: ReferrerAccount = get_state(&banks_client, referrer_account_pubkey).await;
+  assert_eq!(referrer_account.referrer, referrer_kp.pubkey());
+  assert_eq!(referrer_account.num_referrals, 2);
+  assert_eq!(referrer_account.total_fees, 2 * referral_fee);
+  assert_eq!(referrer_account.withdrawn_fees, 0);
+
+  let instruction = withdraw_referral_fees(hit_my_bet::ID, referrer_kp.pubkey(), None);
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &referrer_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    banks_client
+      .get_balance(referrer_kp.pubkey())
+      .await
+      .unwrap(),
+    2 * referral_fee
+  );
+  assert_eq!(
+    banks_client
+      .get_balance(referrer_account_pubkey)
+      .await
+      .unwrap(),
+    referrer_rent
+  );
+  let referrer_account: ReferrerAccount = get_state(&banks_client, referrer_account_pubkey).await;
+  assert_eq!(referrer_account.withdrawn_fees, 2 * referral_fee);
+
+  // Anyone else signing gets a different referrer account.
+  let mut instruction = withdraw_referral_fees(hit_my_bet::ID, payer.pubkey(), None);
+  instruction.accounts[1].pubkey = referrer_account_pubkey;
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidProgramDerivedAddress.into())
+    )
+  );
+}
+
+#[tokio::test]
+async fn test_token_referral_fees() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let vault_pubkey = vault_pubkey(&market_pubkey);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+  let mint = create_mint(&mut banks_client, &mut recent_blockhash, &payer).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams {
+      fees: Some(MarketFees {
+        referral_fee_bps: 100,
+        ..Default::default()
+      }),
+      ..Default::default()
+    },
+    Some(CollateralAccounts {
+      mint,
+      vault: vault_pubkey,
+      token_program: spl_token::ID,
+      funding_account: None,
+    }),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let referrer_kp = Keypair::new();
+  let (referrer_vault_pubkey, _) =
+    ReferrerAccount::find_vault_address(&hit_my_bet::ID, &referrer_kp.pubkey(), &mint);
+  let (mint_fees_pubkey, _) =
+    ReferrerMintFees::find_address(&hit_my_bet::ID, &referrer_kp.pubkey(), &mint);
+
+  // Two referred bets of 3 votes each, the first one creating the referrer vault and totals.
+  for _ in 0..2 {
+    let voter_kp = funded_keypair(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      LAMPORTS_PER_SOL,
+    )
+    .await;
+    let token_account = create_token_account(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      &mint,
+      &voter_kp.pubkey(),
+      10 * VOTE_PRICE,
+    )
+    .await;
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      UserVote::Yes,
+      3,
+      Some(UserCollateralAccounts {
+        token_account,
+        mint,
+        vault: vault_pubkey,
+        token_program: spl_token::ID,
+      }),
+      None,
+      Some(referrer_kp.pubkey()),
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+  }
+
+  let referral_fee = 3 * VOTE_PRICE / 100;
+  assert_eq!(
+    get_token_balance(&banks_client, vault_pubkey).await,
+    6 * VOTE_PRICE
+  );
+  assert_eq!(
+    get_token_balance(&banks_client, referrer_vault_pubkey).await,
+    2 * referral_fee
+  );
+  let mint_fees: ReferrerMintFees = get_state(&banks_client, mint_fees_pubkey).await;
+  assert_eq!(mint_fees.referrer, referrer_kp.pubkey());
+  assert_eq!(mint_fees.mint, mint);
+  assert_eq!(mint_fees.total_fees, 2 * referral_fee);
+  assert_eq!(mint_fees.withdrawn_fees, 0);
+
+  // Lamport totals are left to native SOL markets.
+  let (referrer_account_pubkey, _) =
+    ReferrerAccount::find_address(&hit_my_bet::ID, &referrer_kp.pubkey());
+  let referrer_account: ReferrerAccount = get_state(&banks_client, referrer_account_pubkey).await;
+  assert_eq!(referrer_account.num_referrals, 2);
+  assert_eq!(referrer_account.total_fees, 0);
+
+  let referrer_token_account = create_token_account(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    &mint,
+    &referrer_kp.pubkey(),
+    0,
+  )
+  .await;
+  let instruction = withdraw_referral_fees(
+    hit_my_bet::ID,
+    referrer_kp.pubkey(),
+    Some(UserCollateralAccounts {
+      token_account: referrer_token_account,
+      mint,
+      vault: referrer_vault_pubkey,
+      token_program: spl_token::ID,
+    }),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &referrer_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    get_token_balance(&banks_client, referrer_token_account).await,
+    2 * referral_fee
+  );
+  assert_eq!(
+    get_token_balance(&banks_client, referrer_vault_pubkey).await,
+    0
+  );
+  let mint_fees: ReferrerMintFees = get_state(&banks_client, mint_fees_pubkey).await;
+  assert_eq!(mint_fees.total_fees, 2 * referral_fee);
+  assert_eq!(mint_fees.withdrawn_fees, 2 * referral_fee);
+}
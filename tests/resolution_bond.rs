@@ -0,0 +1,303 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config};
+use helpers::{funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, warp_clock};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  dispute_resolution, finalize_resolution, init_prediction_market, resolve_market,
+  resolve_market_admin, MarketParams, ResolutionBondAccounts,
+};
+use hit_my_bet::state::{
+  MarketKind, MarketPricing, MarketResolution, PredictionMarket, ResolutionStatus, Treasury,
+};
+use solana_program::hash::Hash;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+const DISPUTE_PERIOD: i64 = 3_600;
+
+const BOND: u64 = LAMPORTS_PER_SOL / 2;
+
+/// Bonded market whose betting has closed, with a bond proposing a yes resolution.
+struct ProposedMarket {
+  context: ProgramTestContext,
+  recent_blockhash: Hash,
+  payer: Keypair,
+  admin_kp: Keypair,
+  proposer_kp: Keypair,
+}
+
+fn custom_error(error: HitMyBetError) -> TransactionError {
+  TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+}
+
+async fn setup_proposed_market() -> ProposedMarket {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let mut context = test.start_with_context().await;
+  let payer = context.payer.insecure_clone();
+  let mut recent_blockhash = context.last_blockhash;
+  let admin_kp = setup_program_config(&context.banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&context.banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let init_market = |dispute_period| {
+    init_prediction_market(
+      hit_my_bet::ID,
+      payer.pubkey(),
+      market_pubkey,
+      resolver_kp.pubkey(),
+      config_pubkey(),
+      *MARKET_ID,
+      timestamp + 60,
+      MarketKind::Binary,
+      MarketPricing::Parimutuel,
+      MarketParams {
+        dispute_period,
+        resolution_bond: Some(BOND),
+        ..Default::default()
+      },
+      None,
+    )
+  };
+
+  // Proposals need a dispute period to be challenged in.
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[init_market(None)],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::InvalidResolutionBond)
+  );
+
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[init_market(Some(DISPUTE_PERIOD))],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let proposer_kp = funded_keypair(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    proposer_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction.clone()],
+    &[&payer, &proposer_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::MarketIsOpen)
+  );
+
+  warp_clock(&mut context, 60).await;
+
+  let market_before = context
+    .banks_client
+    .get_balance(market_pubkey)
+    .await
+    .unwrap();
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &proposer_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    context
+      .banks_client
+      .get_balance(market_pubkey)
+      .await
+      .unwrap()
+      - market_before,
+    BOND
+  );
+  let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+  assert_eq!(market.resolution_status, ResolutionStatus::Proposed);
+  assert_eq!(market.proposer, Some(proposer_kp.pubkey()));
+  assert_eq!(market.proposer_bond, BOND);
+
+  ProposedMarket {
+    context,
+    recent_blockhash,
+    payer,
+    admin_kp,
+    proposer_kp,
+  }
+}
+
+#[tokio::test]
+async fn test_unchallenged_proposal_returns_bond() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let ProposedMarket {
+    mut context,
+    mut recent_blockhash,
+    payer,
+    proposer_kp,
+    ..
+  } = setup_proposed_market().await;
+
+  let instruction = finalize_resolution(hit_my_bet::ID, market_pubkey, proposer_kp.pubkey());
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction.clone()],
+    &[&payer],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::ResolutionNotFinal)
+  );
+
+  warp_clock(&mut context, DISPUTE_PERIOD).await;
+
+  let proposer_before = context
+    .banks_client
+    .get_balance(proposer_kp.pubkey())
+    .await
+    .unwrap();
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    context
+      .banks_client
+      .get_balance(proposer_kp.pubkey())
+      .await
+      .unwrap()
+      - proposer_before,
+    BOND
+  );
+  let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+  assert_eq!(market.resolution_status, ResolutionStatus::Final);
+  assert_eq!(market.proposer_bond, 0);
+}
+
+#[tokio::test]
+async fn test_admin_settles_challenged_proposal() {
+  for proposal_upheld in [true, false] {
+    let market_pubkey = market_pubkey(MARKET_ID);
+    let (treasury_pubkey, _) = Treasury::find_address(&hit_my_bet::ID);
+    let ProposedMarket {
+      mut context,
+      mut recent_blockhash,
+      payer,
+      admin_kp,
+      proposer_kp,
+    } = setup_proposed_market().await;
+
+    let challenger_kp = funded_keypair(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &payer,
+      LAMPORTS_PER_SOL,
+    )
+    .await;
+    let instruction =
+      dispute_resolution(hit_my_bet::ID, challenger_kp.pubkey(), market_pubkey, None);
+    let transaction_result = process(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&payer, &challenger_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+    assert_eq!(market.resolution_status, ResolutionStatus::UnderDispute);
+    assert_eq!(market.challenger, Some(challenger_kp.pubkey()));
+    assert_eq!(market.challenger_bond, BOND);
+
+    let balances = [
+      proposer_kp.pubkey(),
+      challenger_kp.pubkey(),
+      treasury_pubkey,
+    ];
+    let mut balances_before = Vec::new();
+    for pubkey in balances {
+      balances_before.push(context.banks_client.get_balance(pubkey).await.unwrap());
+    }
+
+    let resolution = if proposal_upheld {
+      MarketResolution::Yes
+    } else {
+      MarketResolution::No
+    };
+    let instruction = resolve_market_admin(
+      hit_my_bet::ID,
+      admin_kp.pubkey(),
+      market_pubkey,
+      config_pubkey(),
+      resolution,
+      Some(ResolutionBondAccounts {
+        proposer: proposer_kp.pubkey(),
+        challenger: Some(challenger_kp.pubkey()),
+      }),
+    );
+    let transaction_result = process(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&payer, &admin_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+
+    // The winner gets its bond back and half of the loser's; the treasury takes the other half.
+    let expected_gains = if proposal_upheld {
+      [BOND + BOND / 2, 0, BOND / 2]
+    } else {
+      [0, BOND + BOND / 2, BOND / 2]
+    };
+    for ((pubkey, before), gain) in balances.iter().zip(balances_before).zip(expected_gains) {
+      assert_eq!(
+        context.banks_client.get_balance(*pubkey).await.unwrap() - before,
+        gain
+      );
+    }
+    let market: PredictionMarket = get_state(&context.banks_client, market_pubkey).await;
+    assert_eq!(market.resolution, resolution);
+    assert_eq!(market.resolution_status, ResolutionStatus::Final);
+    assert_eq!(market.proposer_bond, 0);
+    assert_eq!(market.challenger_bond, 0);
+  }
+}
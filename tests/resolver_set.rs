@@ -0,0 +1,359 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config};
+use helpers::{get_state, get_unix_timestamp, market_pubkey, process, warp_clock};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  init_prediction_market, resolve_market, resolve_market_admin, submit_resolution_vote,
+  MarketParams,
+};
+use hit_my_bet::state::{
+  MarketKind, MarketPricing, MarketResolution, PredictionMarket, ResolutionStatus, ResolverSet,
+  ResolverSetParams,
+};
+use solana_program::hash::Hash;
+use solana_program_test::{processor, BanksClient, ProgramTest, ProgramTestContext};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+/// Binary market resolved by three resolvers. The banks client stops once `context` is dropped.
+struct QuorumMarket {
+  context: ProgramTestContext,
+  banks_client: BanksClient,
+  recent_blockhash: Hash,
+  payer: Keypair,
+  admin_kp: Keypair,
+  resolver_kp: Keypair,
+  resolvers: Vec<Keypair>,
+}
+
+fn custom_error(error: HitMyBetError) -> TransactionError {
+  TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+}
+
+async fn setup_quorum_market(threshold: u8) -> QuorumMarket {
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+  let context = test.start_with_context().await;
+  let mut banks_client = context.banks_client.clone();
+  let payer = context.payer.insecure_clone();
+  let mut recent_blockhash = context.last_blockhash;
+  let admin_kp = setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let resolvers = vec![Keypair::new(), Keypair::new(), Keypair::new()];
+  let init_market = |resolvers: Vec<_>, threshold| {
+    init_prediction_market(
+      hit_my_bet::ID,
+      payer.pubkey(),
+      market_pubkey(MARKET_ID),
+      resolver_kp.pubkey(),
+      config_pubkey(),
+      *MARKET_ID,
+      timestamp + 60,
+      MarketKind::Binary,
+      MarketPricing::Parimutuel,
+      MarketParams {
+        resolver_set: Some(ResolverSetParams {
+          resolvers,
+          threshold,
+        }),
+        ..Default::default()
+      },
+      None,
+    )
+  };
+
+  let pubkeys: Vec<_> = resolvers.iter().map(|resolver| resolver.pubkey()).collect();
+  for (resolvers, threshold) in [
+    (vec![pubkeys[0], pubkeys[1], pubkeys[0]], 2),
+    (pubkeys.clone(), 0),
+    (pubkeys.clone(), 4),
+  ] {
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[init_market(resolvers, threshold)],
+      &[&payer, &resolver_kp],
+    )
+    .await;
+    assert_eq!(
+      transaction_result.unwrap_err().unwrap(),
+      custom_error(HitMyBetError::InvalidResolverSet)
+    );
+  }
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[init_market(pubkeys, threshold)],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  QuorumMarket {
+    context,
+    banks_client,
+    recent_blockhash,
+    payer,
+    admin_kp,
+    resolver_kp,
+    resolvers,
+  }
+}
+
+#[tokio::test]
+async fn test_quorum_resolves_market() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let QuorumMarket {
+    context: _context,
+    mut banks_client,
+    mut recent_blockhash,
+    payer,
+    resolver_kp,
+    resolvers,
+    ..
+  } = setup_quorum_market(2).await;
+
+  // The market's single resolver is superseded by the set.
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::InvalidResolver)
+  );
+
+  let outsider_kp = Keypair::new();
+  let instruction = submit_resolution_vote(
+    hit_my_bet::ID,
+    outsider_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &outsider_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::InvalidResolver)
+  );
+
+  let vote = |resolver: &Keypair, resolution| {
+    submit_resolution_vote(hit_my_bet::ID, resolver.pubkey(), market_pubkey, resolution)
+  };
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[vote(&resolvers[0], MarketResolution::Yes)],
+    &[&payer, &resolvers[0]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.resolution, MarketResolution::Unresolved);
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[vote(&resolvers[0], MarketResolution::No)],
+    &[&payer, &resolvers[0]],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::ResolverAlreadyVoted)
+  );
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[vote(&resolvers[2], MarketResolution::Yes)],
+    &[&payer, &resolvers[2]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.resolution, MarketResolution::Yes);
+  assert_eq!(market.resolution_status, ResolutionStatus::Final);
+  let (resolver_set_pubkey, _) = ResolverSet::find_address(&hit_my_bet::ID, &market_pubkey);
+  let resolver_set: ResolverSet = get_state(&banks_client, resolver_set_pubkey).await;
+  assert_eq!(
+    resolver_set.votes[..3],
+    [
+      Some(MarketResolution::Yes),
+      None,
+      Some(MarketResolution::Yes)
+    ]
+  );
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[vote(&resolvers[1], MarketResolution::No)],
+    &[&payer, &resolvers[1]],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::MarketIsResolved)
+  );
+}
+
+#[tokio::test]
+async fn test_admin_resolves_deadlocked_set() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let QuorumMarket {
+    context: _context,
+    mut banks_client,
+    mut recent_blockhash,
+    payer,
+    admin_kp,
+    resolvers,
+    ..
+  } = setup_quorum_market(3).await;
+
+  for (resolver, resolution) in resolvers
+    .iter()
+    .zip([MarketResolution::Yes, MarketResolution::No])
+  {
+    let instruction =
+      submit_resolution_vote(hit_my_bet::ID, resolver.pubkey(), market_pubkey, resolution);
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&payer, resolver],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+  }
+
+  // A unanimous threshold cannot be reached once two resolvers disagree.
+  let instruction = submit_resolution_vote(
+    hit_my_bet::ID,
+    resolvers[2].pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolvers[2]],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::ResolverSetDeadlocked)
+  );
+
+  let instruction = resolve_market_admin(
+    hit_my_bet::ID,
+    admin_kp.pubkey(),
+    market_pubkey,
+    config_pubkey(),
+    MarketResolution::No,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.resolution, MarketResolution::No);
+  assert_eq!(market.resolution_status, ResolutionStatus::Final);
+}
+
+#[tokio::test]
+async fn test_admin_waits_for_live_set() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let QuorumMarket {
+    mut context,
+    mut banks_client,
+    mut recent_blockhash,
+    payer,
+    admin_kp,
+    resolvers,
+    ..
+  } = setup_quorum_market(2).await;
+
+  let instruction = submit_resolution_vote(
+    hit_my_bet::ID,
+    resolvers[0].pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolvers[0]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // Betting has closed, but the remaining resolvers can still reach the threshold.
+  warp_clock(&mut context, 61).await;
+  let instruction = resolve_market_admin(
+    hit_my_bet::ID,
+    admin_kp.pubkey(),
+    market_pubkey,
+    config_pubkey(),
+    MarketResolution::No,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction.clone()],
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    custom_error(HitMyBetError::MarketIsOpen)
+  );
+
+  warp_clock(&mut context, ResolverSet::RESOLUTION_TIMEOUT).await;
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.resolution, MarketResolution::No);
+  assert_eq!(market.resolution_status, ResolutionStatus::Final);
+}
@@ -0,0 +1,153 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::{funded_keypair, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  claim_market, init_prediction_market, make_scalar_prediction, resolve_market, MarketParams,
+};
+use hit_my_bet::state::{MarketKind, MarketPricing, MarketResolution, ScalarPosition};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[tokio::test]
+async fn test_scalar_market() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Scalar { min: 0, max: 100 },
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let long_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  let short_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  for (voter, position, num_votes) in [
+    (&long_kp, ScalarPosition::Long, 3),
+    (&short_kp, ScalarPosition::Short, 1),
+  ] {
+    let instruction = make_scalar_prediction(
+      hit_my_bet::ID,
+      voter.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter.pubkey()),
+      position,
+      num_votes,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[voter],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+  }
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Scalar(101),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidScalarRange.into())
+    )
+  );
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Scalar(25),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  for (voter, payout) in [(&long_kp, VOTE_PRICE), (&short_kp, 3 * VOTE_PRICE)] {
+    let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+    let instruction = claim_market(
+      hit_my_bet::ID,
+      voter.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter.pubkey()),
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[voter],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+
+    let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+    assert_eq!(balance_before - balance_after, payout);
+  }
+}
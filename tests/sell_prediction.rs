@@ -0,0 +1,280 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  init_prediction_market, make_prediction, resolve_market, sell_prediction, MarketParams,
+};
+use hit_my_bet::state::{
+  MarketKind, MarketPricing, MarketResolution, PredictionMarket, UserPrediction, UserVote,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+#[tokio::test]
+async fn test_sell_prediction() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let voter_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    10 * LAMPORTS_PER_SOL,
+  )
+  .await;
+  let prediction_pubkey = user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey());
+  let instruction = make_prediction(
+    hit_my_bet::ID,
+    voter_kp.pubkey(),
+    market_pubkey,
+    prediction_pubkey,
+    UserVote::Yes,
+    5,
+    None,
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voter_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  for (vote, num_votes, error) in [
+    (UserVote::No, 1, HitMyBetError::InsufficientVotes),
+    (UserVote::Yes, 6, HitMyBetError::InsufficientVotes),
+  ] {
+    let instruction = sell_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      prediction_pubkey,
+      vote,
+      num_votes,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert_eq!(
+      transaction_result.unwrap_err().unwrap(),
+      TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+    );
+  }
+
+  let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+  let instruction = sell_prediction(
+    hit_my_bet::ID,
+    voter_kp.pubkey(),
+    market_pubkey,
+    prediction_pubkey,
+    UserVote::Yes,
+    2,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voter_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let refund = 2 * VOTE_PRICE - 2 * VOTE_PRICE * PredictionMarket::EXIT_FEE_BPS / 10_000;
+  let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+  assert_eq!(balance_before - balance_after, refund);
+
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.num_yes, 3);
+  assert_eq!(market.balance_yes, 5 * VOTE_PRICE - refund);
+  let prediction: UserPrediction = get_state(&banks_client, prediction_pubkey).await;
+  assert_eq!(prediction.num_votes_yes, 3);
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let instruction = sell_prediction(
+    hit_my_bet::ID,
+    voter_kp.pubkey(),
+    market_pubkey,
+    prediction_pubkey,
+    UserVote::Yes,
+    1,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voter_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::MarketIsResolved.into())
+    )
+  );
+}
+
+#[tokio::test]
+async fn test_sequential_sellers() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let mut voters = Vec::new();
+  for _ in 0..2 {
+    let voter_kp = funded_keypair(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      10 * LAMPORTS_PER_SOL,
+    )
+    .await;
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      UserVote::Yes,
+      5,
+      None,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    voters.push(voter_kp);
+  }
+
+  // The second seller gets the same refund as the first, not the first seller's exit fee.
+  let exit_fee = 5 * VOTE_PRICE * PredictionMarket::EXIT_FEE_BPS / 10_000;
+  for voter_kp in &voters {
+    let balance_before = banks_client.get_balance(market_pubkey).await.unwrap();
+    let instruction = sell_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      UserVote::Yes,
+      5,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    let balance_after = banks_client.get_balance(market_pubkey).await.unwrap();
+    assert_eq!(balance_before - balance_after, 5 * VOTE_PRICE - exit_fee);
+  }
+
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.num_yes, 0);
+  assert_eq!(market.balance_yes, 2 * exit_fee);
+}
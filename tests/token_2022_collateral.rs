@@ -0,0 +1,294 @@
+mod helpers;
+
+use helpers::program_config::{add_program_data, config_pubkey, setup_program_config, VOTE_PRICE};
+use helpers::token::{
+  create_permanent_delegate_mint, create_token_2022_account, create_transfer_fee_mint,
+  get_token_balance, vault_pubkey,
+};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  claim_market, init_prediction_market, make_prediction, resolve_market, CollateralAccounts,
+  MarketParams, UserCollateralAccounts,
+};
+use hit_my_bet::state::{MarketKind, MarketPricing, MarketResolution, PredictionMarket, UserVote};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+const FEE_BASIS_POINTS: u16 = 100;
+
+fn after_fee(amount: u64) -> u64 {
+  amount - amount * u64::from(FEE_BASIS_POINTS) / 10_000
+}
+
+#[tokio::test]
+async fn test_transfer_fee_market() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let vault_pubkey = vault_pubkey(&market_pubkey);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+  let mint = create_transfer_fee_mint(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    FEE_BASIS_POINTS,
+    u64::MAX,
+  )
+  .await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    Some(CollateralAccounts {
+      mint,
+      vault: vault_pubkey,
+      token_program: spl_token_2022::ID,
+      funding_account: None,
+    }),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let yes_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  let no_kp = funded_keypair(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    LAMPORTS_PER_SOL,
+  )
+  .await;
+  let mut token_accounts = Vec::new();
+  for (voter, vote) in [(&yes_kp, UserVote::Yes), (&no_kp, UserVote::No)] {
+    let token_account = create_token_2022_account(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &payer,
+      &mint,
+      &voter.pubkey(),
+      10 * VOTE_PRICE,
+    )
+    .await;
+    token_accounts.push(token_account);
+
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter.pubkey()),
+      vote,
+      2,
+      Some(UserCollateralAccounts {
+        token_account,
+        mint,
+        vault: vault_pubkey,
+        token_program: spl_token_2022::ID,
+      }),
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[voter],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+  }
+
+  let market: PredictionMarket = get_state(&banks_client, market_pubkey).await;
+  assert_eq!(market.balance_yes, after_fee(2 * VOTE_PRICE));
+  assert_eq!(market.balance_no, after_fee(2 * VOTE_PRICE));
+  assert_eq!(
+    get_token_balance(&banks_client, vault_pubkey).await,
+    market.balance_yes + market.balance_no
+  );
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    yes_kp.pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &yes_kp.pubkey()),
+    Some(UserCollateralAccounts {
+      token_account: token_accounts[0],
+      mint,
+      vault: vault_pubkey,
+      token_program: spl_token_2022::ID,
+    }),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&yes_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  assert_eq!(get_token_balance(&banks_client, vault_pubkey).await, 0);
+  assert_eq!(
+    get_token_balance(&banks_client, token_accounts[0]).await,
+    8 * VOTE_PRICE + after_fee(market.balance_yes + market.balance_no)
+  );
+}
+
+#[tokio::test]
+async fn test_permanent_delegate_mint_rejected() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+  let mint = create_permanent_delegate_mint(&mut banks_client, &mut recent_blockhash, &payer).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    Some(CollateralAccounts {
+      mint,
+      vault: vault_pubkey(&market_pubkey),
+      token_program: spl_token_2022::ID,
+      funding_account: None,
+    }),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::UnsupportedMint.into())
+    )
+  );
+}
+
+#[tokio::test]
+async fn test_cpmm_rejects_transfer_fee_mint() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+  let mint = create_transfer_fee_mint(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &payer,
+    FEE_BASIS_POINTS,
+    u64::MAX,
+  )
+  .await;
+
+  // Buys would credit the reserves with the quoted cost while the vault receives it net of fees.
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Cpmm,
+    MarketParams::default(),
+    Some(CollateralAccounts {
+      mint,
+      vault: vault_pubkey(&market_pubkey),
+      token_program: spl_token_2022::ID,
+      funding_account: None,
+    }),
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::UnsupportedMint.into())
+    )
+  );
+}
@@ -0,0 +1,398 @@
+mod helpers;
+
+use helpers::program_config::{
+  add_program_data, config_pubkey, setup_program_config, CREATE_MARKET_FEE,
+};
+use helpers::token::{create_mint, create_token_account, get_token_balance, vault_pubkey};
+use helpers::{
+  funded_keypair, get_state, get_unix_timestamp, market_pubkey, process, user_prediction_pubkey,
+  warp_clock,
+};
+use hit_my_bet::entrypoint::process_instruction;
+use hit_my_bet::error::HitMyBetError;
+use hit_my_bet::instruction::{
+  claim_market, init_prediction_market, make_prediction, resolve_market, sweep_unclaimed,
+  withdraw_treasury, CollateralAccounts, MarketParams, UserCollateralAccounts,
+};
+use hit_my_bet::state::{
+  MarketFees, MarketKind, MarketPricing, MarketResolution, PredictionMarket, Treasury, UserVote,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::rent::Rent;
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const MARKET_ID: &[u8; 16] = b"market_id_16_chr";
+
+const CLAIM_PERIOD: i64 = 3_600;
+
+#[tokio::test]
+async fn test_treasury() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let (treasury_pubkey, _) = Treasury::find_address(&hit_my_bet::ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  let admin_kp = setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // The creation fee goes to the treasury; the market only holds its rent.
+  let rent = Rent::default();
+  assert_eq!(
+    banks_client.get_balance(market_pubkey).await.unwrap(),
+    rent.minimum_balance(PredictionMarket::LEN)
+  );
+  let treasury_rent = rent.minimum_balance(Treasury::LEN);
+  assert_eq!(
+    banks_client.get_balance(treasury_pubkey).await.unwrap(),
+    treasury_rent + CREATE_MARKET_FEE
+  );
+  let treasury: Treasury = get_state(&banks_client, treasury_pubkey).await;
+  assert_eq!(treasury.total_fees, CREATE_MARKET_FEE);
+
+  let recipient = Keypair::new().pubkey();
+  for (signer, amount, error) in [
+    (&payer, 1, HitMyBetError::InvalidAdmin),
+    (
+      &admin_kp,
+      CREATE_MARKET_FEE + 1,
+      HitMyBetError::InsufficientTreasuryFunds,
+    ),
+  ] {
+    let instruction = withdraw_treasury(
+      hit_my_bet::ID,
+      signer.pubkey(),
+      config_pubkey(),
+      recipient,
+      amount,
+      None,
+    );
+    let transaction_result = process(
+      &mut banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&payer, signer],
+    )
+    .await;
+    assert_eq!(
+      transaction_result.unwrap_err().unwrap(),
+      TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+    );
+  }
+
+  let instruction = withdraw_treasury(
+    hit_my_bet::ID,
+    admin_kp.pubkey(),
+    config_pubkey(),
+    recipient,
+    CREATE_MARKET_FEE,
+    None,
+  );
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    banks_client.get_balance(recipient).await.unwrap(),
+    CREATE_MARKET_FEE
+  );
+  assert_eq!(
+    banks_client.get_balance(treasury_pubkey).await.unwrap(),
+    treasury_rent
+  );
+}
+
+#[tokio::test]
+async fn test_treasury_total_fees() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let mut context = test.start_with_context().await;
+  let payer = context.payer.insecure_clone();
+  let mut recent_blockhash = context.last_blockhash;
+  setup_program_config(&context.banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&context.banks_client).await;
+
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams {
+      claim_period: Some(CLAIM_PERIOD),
+      fees: Some(MarketFees {
+        protocol_fee_bps: 100,
+        creator_fee_bps: 0,
+        referral_fee_bps: 0,
+      }),
+      ..Default::default()
+    },
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    treasury_fees(&context.banks_client).await,
+    (CREATE_MARKET_FEE, CREATE_MARKET_FEE)
+  );
+
+  let mut voters = Vec::new();
+  for vote in [UserVote::Yes, UserVote::Yes, UserVote::No] {
+    let voter_kp = funded_keypair(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &payer,
+      10 * LAMPORTS_PER_SOL,
+    )
+    .await;
+    let instruction = make_prediction(
+      hit_my_bet::ID,
+      voter_kp.pubkey(),
+      market_pubkey,
+      user_prediction_pubkey(&market_pubkey, &voter_kp.pubkey()),
+      vote,
+      3,
+      None,
+      None,
+      None,
+    );
+    let transaction_result = process(
+      &mut context.banks_client,
+      &mut recent_blockhash,
+      &[instruction],
+      &[&voter_kp],
+    )
+    .await;
+    assert!(transaction_result.is_ok());
+    voters.push(voter_kp);
+  }
+
+  let instruction = resolve_market(
+    hit_my_bet::ID,
+    resolver_kp.pubkey(),
+    market_pubkey,
+    MarketResolution::Yes,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  // The protocol fee of a winning claim is counted.
+  let instruction = claim_market(
+    hit_my_bet::ID,
+    voters[0].pubkey(),
+    market_pubkey,
+    user_prediction_pubkey(&market_pubkey, &voters[0].pubkey()),
+    None,
+    None,
+  );
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&voters[0]],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let (total_fees, received) = treasury_fees(&context.banks_client).await;
+  assert!(total_fees > CREATE_MARKET_FEE);
+  assert_eq!(total_fees, received);
+
+  // So are the winnings swept after the claim period.
+  warp_clock(&mut context, CLAIM_PERIOD).await;
+  let instruction = sweep_unclaimed(hit_my_bet::ID, payer.pubkey(), market_pubkey, None);
+  let transaction_result = process(
+    &mut context.banks_client,
+    &mut recent_blockhash,
+    &[instruction],
+    &[&payer],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  let (swept_total_fees, received) = treasury_fees(&context.banks_client).await;
+  assert!(swept_total_fees > total_fees);
+  assert_eq!(swept_total_fees, received);
+}
+
+#[tokio::test]
+async fn test_withdraw_treasury_tokens() {
+  let market_pubkey = market_pubkey(MARKET_ID);
+  let mut test = ProgramTest::new(
+    "hit_my_bet",
+    hit_my_bet::ID,
+    processor!(process_instruction),
+  );
+  add_program_data(&mut test);
+
+  let (mut banks_client, payer, mut recent_blockhash) = test.start().await;
+  let admin_kp = setup_program_config(&banks_client, &payer, recent_blockhash).await;
+  let timestamp = get_unix_timestamp(&banks_client).await;
+  let mint = create_mint(&mut banks_client, &mut recent_blockhash, &payer).await;
+  let (treasury_vault, _) = Treasury::find_vault_address(&hit_my_bet::ID, &mint);
+
+  // A token market creates the treasury vault of its collateral mint.
+  let resolver_kp = Keypair::new();
+  let instruction = init_prediction_market(
+    hit_my_bet::ID,
+    payer.pubkey(),
+    market_pubkey,
+    resolver_kp.pubkey(),
+    config_pubkey(),
+    *MARKET_ID,
+    timestamp + 60,
+    MarketKind::Binary,
+    MarketPricing::Parimutuel,
+    MarketParams::default(),
+    Some(CollateralAccounts {
+      mint,
+      vault: vault_pubkey(&market_pubkey),
+      token_program: spl_token::ID,
+      funding_account: None,
+    }),
+  );
+  let mint_to_vault = spl_token::instruction::mint_to(
+    &spl_token::ID,
+    &mint,
+    &treasury_vault,
+    &payer.pubkey(),
+    &[],
+    100,
+  )
+  .unwrap();
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[instruction, mint_to_vault],
+    &[&payer, &resolver_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+
+  let recipient = Keypair::new().pubkey();
+  let mut token_accounts = Vec::new();
+  for owner in [Keypair::new().pubkey(), recipient] {
+    token_accounts.push(
+      create_token_account(
+        &mut banks_client,
+        &mut recent_blockhash,
+        &payer,
+        &mint,
+        &owner,
+        0,
+      )
+      .await,
+    );
+  }
+  let withdraw = |token_account| {
+    withdraw_treasury(
+      hit_my_bet::ID,
+      admin_kp.pubkey(),
+      config_pubkey(),
+      recipient,
+      100,
+      Some(UserCollateralAccounts {
+        token_account,
+        mint,
+        vault: treasury_vault,
+        token_program: spl_token::ID,
+      }),
+    )
+  };
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[withdraw(token_accounts[0])],
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert_eq!(
+    transaction_result.unwrap_err().unwrap(),
+    TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(HitMyBetError::InvalidRecipient.into())
+    )
+  );
+
+  let transaction_result = process(
+    &mut banks_client,
+    &mut recent_blockhash,
+    &[withdraw(token_accounts[1])],
+    &[&payer, &admin_kp],
+  )
+  .await;
+  assert!(transaction_result.is_ok());
+  assert_eq!(
+    get_token_balance(&banks_client, token_accounts[1]).await,
+    100
+  );
+  assert_eq!(get_token_balance(&banks_client, treasury_vault).await, 0);
+}
+
+/// Total fees recorded by the treasury and lamports it holds above its rent.
+async fn treasury_fees(banks_client: &BanksClient) -> (u64, u64) {
+  let (treasury_pubkey, _) = Treasury::find_address(&hit_my_bet::ID);
+  let treasury: Treasury = get_state(banks_client, treasury_pubkey).await;
+  let balance = banks_client.get_balance(treasury_pubkey).await.unwrap();
+  (
+    treasury.total_fees,
+    balance - Rent::default().minimum_balance(Treasury::LEN),
+  )
+}